@@ -1,5 +1,7 @@
 //! Definition of keyboard and mouse button.
 
+use std::fmt;
+use std::str::FromStr;
 use variant_count::VariantCount;
 
 /// A button input action.
@@ -19,191 +21,238 @@ pub enum ButtonKind {
     Mouse,
 }
 
-/// Keyboard or mouse buttons.
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, VariantCount)]
-pub enum Button {
-    LeftButton,
-    RightButton,
-    MiddleButton,
-    SideButton1,
-    SideButton2,
-
-    #[cfg(feature = "us-keyboard-layout")]
-    Tilde,
-    #[cfg(feature = "japanese-keyboard-layout")]
-    HankakuZenkaku,
-
-    Key1,
-    Key2,
-    Key3,
-    Key4,
-    Key5,
-    Key6,
-    Key7,
-    Key8,
-    Key9,
-    Key0,
-    Minus,
-
-    #[cfg(feature = "us-keyboard-layout")]
-    Equal,
-    #[cfg(feature = "japanese-keyboard-layout")]
-    Hat,
-
-    #[cfg(feature = "japanese-keyboard-layout")]
-    Yen,
-
-    Backspace,
-    Tab,
-    Q,
-    W,
-    E,
-    R,
-    T,
-    Y,
-    U,
-    I,
-    O,
-    P,
-
-    #[cfg(feature = "us-keyboard-layout")]
-    OpenSquareBracket,
-    #[cfg(feature = "japanese-keyboard-layout")]
-    At,
-
-    #[cfg(feature = "us-keyboard-layout")]
-    CloseSquareBracket,
-    #[cfg(feature = "japanese-keyboard-layout")]
-    OpenSquareBracket,
-
-    #[cfg(feature = "us-keyboard-layout")]
-    CapsLock,
-    #[cfg(feature = "japanese-keyboard-layout")]
-    Eisu,
-
-    A,
-    S,
-    D,
-    F,
-    G,
-    H,
-    J,
-    K,
-    L,
-
-    #[cfg(feature = "us-keyboard-layout")]
-    SemiColon,
-    #[cfg(feature = "japanese-keyboard-layout")]
-    SemiColon,
-
-    #[cfg(feature = "us-keyboard-layout")]
-    SingleQuote,
-    #[cfg(feature = "japanese-keyboard-layout")]
-    Colon,
-
-    #[cfg(feature = "japanese-keyboard-layout")]
-    CloseSquareBracket,
-
-    Enter,
-    LShift,
-    Z,
-    X,
-    C,
-    V,
-    B,
-    N,
-    M,
-    Comma,
-    Dot,
-    Slash,
-
-    #[cfg(feature = "japanese-keyboard-layout")]
-    BackSlash,
-
-    RShift,
-    LCtrl,
-    LSuper,
-    LAlt,
-
-    #[cfg(feature = "japanese-keyboard-layout")]
-    Muhenkan,
-
-    Space,
-
-    #[cfg(feature = "japanese-keyboard-layout")]
-    Henkan,
-
-    #[cfg(feature = "japanese-keyboard-layout")]
-    KatakanaHiragana,
-
-    RAlt,
-    RSuper,
-    Application,
-    RCtrl,
-    Insert,
-    Delete,
-    LeftArrow,
-    Home,
-    End,
-    UpArrow,
-    DownArrow,
-    PageUp,
-    PageDown,
-    RightArrow,
-    Numpad1,
-    Numpad2,
-    Numpad3,
-    Numpad4,
-    Numpad5,
-    Numpad6,
-    Numpad7,
-    Numpad8,
-    Numpad9,
-    Numpad0,
-    NumpadDot,
-    NumpadSlash,
-    NumpadAsterisk,
-    NumpadMinus,
-    NumpadPlus,
-    Esc,
-    F1,
-    F2,
-    F3,
-    F4,
-    F5,
-    F6,
-    F7,
-    F8,
-    F9,
-    F10,
-    F11,
-    F12,
-    F13,
-    F14,
-    F15,
-    F16,
-    F17,
-    F18,
-    F19,
-    F20,
-    F21,
-    F22,
-    F23,
-    F24,
-    PrintScreen,
-
-    VolumeMute,
-    VolumeDown,
-    VolumeUp,
-    MediaNext,
-    MediaPrevious,
-    MediaStop,
-    MediaPlayPause,
-
-    Shift,
-    Ctrl,
-    Alt,
-    Super,
+/// Defines [`Button`] along with the conversions from and to the names of its variants.
+macro_rules! define_button {
+    (
+        $(#[$attr:meta])*
+        pub enum Button {
+            $(
+                $(#[cfg($cfg:meta)])*
+                $variant:ident,
+            )*
+        }
+    ) => {
+        $(#[$attr])*
+        pub enum Button {
+            $(
+                $(#[cfg($cfg)])*
+                $variant,
+            )*
+        }
+
+        impl Button {
+            /// Returns the name of the variant.
+            fn name(self) -> &'static str {
+                match self {
+                    $(
+                        $(#[cfg($cfg)])*
+                        Button::$variant => stringify!($variant),
+                    )*
+                }
+            }
+
+            fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(
+                        $(#[cfg($cfg)])*
+                        stringify!($variant) => Some(Button::$variant),
+                    )*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+define_button! {
+    /// Keyboard or mouse buttons.
+    #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, VariantCount)]
+    pub enum Button {
+        LeftButton,
+        RightButton,
+        MiddleButton,
+        SideButton1,
+        SideButton2,
+
+        #[cfg(feature = "us-keyboard-layout")]
+        Tilde,
+        #[cfg(feature = "japanese-keyboard-layout")]
+        HankakuZenkaku,
+
+        Key1,
+        Key2,
+        Key3,
+        Key4,
+        Key5,
+        Key6,
+        Key7,
+        Key8,
+        Key9,
+        Key0,
+        Minus,
+
+        #[cfg(feature = "us-keyboard-layout")]
+        Equal,
+        #[cfg(feature = "japanese-keyboard-layout")]
+        Hat,
+
+        #[cfg(feature = "japanese-keyboard-layout")]
+        Yen,
+
+        Backspace,
+        Tab,
+        Q,
+        W,
+        E,
+        R,
+        T,
+        Y,
+        U,
+        I,
+        O,
+        P,
+
+        #[cfg(feature = "us-keyboard-layout")]
+        OpenSquareBracket,
+        #[cfg(feature = "japanese-keyboard-layout")]
+        At,
+
+        #[cfg(feature = "us-keyboard-layout")]
+        CloseSquareBracket,
+        #[cfg(feature = "japanese-keyboard-layout")]
+        OpenSquareBracket,
+
+        #[cfg(feature = "us-keyboard-layout")]
+        CapsLock,
+        #[cfg(feature = "japanese-keyboard-layout")]
+        Eisu,
+
+        A,
+        S,
+        D,
+        F,
+        G,
+        H,
+        J,
+        K,
+        L,
+
+        #[cfg(feature = "us-keyboard-layout")]
+        SemiColon,
+        #[cfg(feature = "japanese-keyboard-layout")]
+        SemiColon,
+
+        #[cfg(feature = "us-keyboard-layout")]
+        SingleQuote,
+        #[cfg(feature = "japanese-keyboard-layout")]
+        Colon,
+
+        #[cfg(feature = "japanese-keyboard-layout")]
+        CloseSquareBracket,
+
+        Enter,
+        LShift,
+        Z,
+        X,
+        C,
+        V,
+        B,
+        N,
+        M,
+        Comma,
+        Dot,
+        Slash,
+
+        #[cfg(feature = "japanese-keyboard-layout")]
+        BackSlash,
+
+        RShift,
+        LCtrl,
+        LSuper,
+        LAlt,
+
+        #[cfg(feature = "japanese-keyboard-layout")]
+        Muhenkan,
+
+        Space,
+
+        #[cfg(feature = "japanese-keyboard-layout")]
+        Henkan,
+
+        #[cfg(feature = "japanese-keyboard-layout")]
+        KatakanaHiragana,
+
+        RAlt,
+        RSuper,
+        Application,
+        RCtrl,
+        Insert,
+        Delete,
+        LeftArrow,
+        Home,
+        End,
+        UpArrow,
+        DownArrow,
+        PageUp,
+        PageDown,
+        RightArrow,
+        Numpad1,
+        Numpad2,
+        Numpad3,
+        Numpad4,
+        Numpad5,
+        Numpad6,
+        Numpad7,
+        Numpad8,
+        Numpad9,
+        Numpad0,
+        NumpadDot,
+        NumpadSlash,
+        NumpadAsterisk,
+        NumpadMinus,
+        NumpadPlus,
+        Esc,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        F13,
+        F14,
+        F15,
+        F16,
+        F17,
+        F18,
+        F19,
+        F20,
+        F21,
+        F22,
+        F23,
+        F24,
+        PrintScreen,
+        NumLock,
+        ScrollLock,
+
+        VolumeMute,
+        VolumeDown,
+        VolumeUp,
+        MediaNext,
+        MediaPrevious,
+        MediaStop,
+        MediaPlayPause,
+
+        Shift,
+        Ctrl,
+        Alt,
+        Super,
+    }
 }
 
 impl Button {
@@ -218,3 +267,50 @@ impl Button {
         }
     }
 }
+
+/// An error returned when parsing a [`Button`] from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseButtonError(String);
+
+impl fmt::Display for ParseButtonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown button name: `{}`", self.0)
+    }
+}
+
+impl std::error::Error for ParseButtonError {}
+
+impl FromStr for Button {
+    type Err = ParseButtonError;
+
+    /// Parses the name of the variant, which is the same as the [`Debug`] representation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap_core::button::Button;
+    ///
+    /// assert_eq!("LShift".parse(), Ok(Button::LShift));
+    /// assert!("Foo".parse::<Button>().is_err());
+    /// ```
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Button::from_name(s).ok_or_else(|| ParseButtonError(s.to_owned()))
+    }
+}
+
+impl fmt::Display for Button {
+    /// Writes the name of the variant, which can be parsed by [`FromStr`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap_core::button::Button;
+    ///
+    /// assert_eq!(Button::LShift.to_string(), "LShift");
+    /// ```
+    ///
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -7,6 +7,22 @@
 //! Alternatively, calling [`NativeEventHandler::dispatch`] will notify other programs of the event.
 //! If neither is called, the event is notified.
 //!
+//! If a [`decision_timeout`] is set and neither is called within it, the event is notified
+//! without waiting for the decision. This prevents the input from freezing when the receiver
+//! stalls, and the OS from removing the hook for taking too long.
+//! Such an event is cancelled (See [`NativeEventHandler::is_cancelled`]). The decision on it
+//! has no effect, since other programs have already been notified of it, but a receiver that
+//! keeps track of the state of buttons should still reflect it.
+//!
+//! No [`decision_timeout`] is set by default, but a kill switch is set by default
+//! (See [`kill_switch`](crate::kill_switch)), and while it is set the wait is bounded by
+//! [`KILL_SWITCH_DECISION_TIMEOUT`] (200 ms). So by default, an event whose decision takes
+//! longer than 200 ms is notified and cancelled, and while the receiver has not taken
+//! the previous timed-out event, following events are notified immediately.
+//! To wait for the decision forever, disable the kill switch with
+//! [`kill_switch::set(None)`](crate::kill_switch::set) and leave [`decision_timeout`] `None`;
+//! a stalled receiver then freezes the input system-wide.
+//!
 //! # Warning
 //!
 //! On Windows, Calling function that perform input (e.g. [`Button::press`]) before calling [`NativeEventHandler::block`]
@@ -22,9 +38,24 @@
 //!     native_handler.block();
 //! }
 //! ```
+//!
+//! With the `async` feature, events can also be received with [`EventStream`]
+//! returned by [`install_hook_async`](crate::install_hook_async).
 
 use super::button::{Button, ButtonAction};
-use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use super::kill_switch;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::Waker;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    sync::mpsc::TryRecvError,
+    task::{Context, Poll},
+};
 
 /// Indicates whether to pass the generated event to the next program or not.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -60,25 +91,67 @@ pub struct ButtonEvent {
     /// Whether this event was generated by this program.
     /// If you type on your keyboard and an event is generated, this value will be `false`.
     pub injected: bool,
+
+    /// Whether this event is a press of a button that is already pressed,
+    /// such as an auto-repeat generated while a key is held down.
+    pub is_repeat: bool,
 }
 
+/// An identifier of a monitor.
+///
+/// On Windows, this is the value of the `HMONITOR` handle.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MonitorId(pub isize);
+
 /// Indicates mouse cursor event.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CursorEvent {
     /// Mouse cursor movement `(x, y)`
     pub delta: (i32, i32),
 
+    /// Position of the mouse cursor after the movement `(x, y)`, in screen coordinates.
+    pub position: (i32, i32),
+
+    /// The monitor that contains [`CursorEvent::position`].
+    pub monitor: MonitorId,
+
     /// Whether this event was generated by this program.
     pub injected: bool,
 }
 
+/// Amount of [`WheelEvent::hi_res_delta`] corresponding to one notch of the mouse wheel.
+pub const WHEEL_DELTA: i32 = 120;
+
+/// Axis of mouse wheel rotation.
+///
+/// Only Windows reports horizontal scrolling, from `WM_MOUSEHWHEEL`.
+/// There is no mapping from the Linux `REL_HWHEEL` and `REL_WHEEL_HI_RES` events,
+/// since this library has no Linux backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WheelAxis {
+    /// Vertical scrolling. Upward rotation takes a positive value.
+    Vertical,
+
+    /// Horizontal scrolling. Rightward rotation takes a positive value.
+    Horizontal,
+}
+
 /// Indicates mouse wheel event.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct WheelEvent {
-    /// Amout of mouse wheel rotation
-    /// Upward rotation takes a positive value, downward rotation a negative value.
+    /// Amout of mouse wheel rotation in notches, rounded toward zero.
+    /// Upward and rightward rotation take a positive value,
+    /// downward and leftward rotation a negative value.
     pub delta: i32,
 
+    /// Amount of mouse wheel rotation in units of 1/[`WHEEL_DELTA`] of a notch.
+    /// Smooth-scrolling devices such as touchpads report rotations smaller than a notch,
+    /// for which [`WheelEvent::delta`] is `0`.
+    pub hi_res_delta: i32,
+
+    /// Axis of rotation.
+    pub axis: WheelAxis,
+
     /// Whether this event was generated by this program.
     pub injected: bool,
 }
@@ -100,16 +173,34 @@ pub enum Event {
 #[derive(Debug)]
 pub struct NativeEventHandler {
     tx: Option<Sender<NativeEventOperation>>,
+    cancelled: Arc<AtomicBool>,
 }
 
 impl NativeEventHandler {
-    fn new(tx: Sender<NativeEventOperation>) -> Self {
-        Self { tx: Some(tx) }
+    pub(crate) fn new(tx: Sender<NativeEventOperation>, cancelled: Arc<AtomicBool>) -> Self {
+        Self {
+            tx: Some(tx),
+            cancelled,
+        }
+    }
+
+    /// Returns `true` if the event has already been notified because the [`decision_timeout`]
+    /// has elapsed. The decision on the event has no effect, and the event must not be replaced
+    /// with other inputs, but it should still be reflected in the state kept by the receiver.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns the flag shared with a handler to which the event is forwarded.
+    pub(crate) fn cancellation(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancelled)
     }
 
     /// Decides whether or not to notify by argument.
+    ///
+    /// This has no effect if the event is cancelled.
     pub fn handle(mut self, operation: NativeEventOperation) {
-        self.tx.take().unwrap().send(operation).unwrap();
+        let _ = self.tx.take().unwrap().send(operation);
     }
 
     // Notifies an event.
@@ -126,34 +217,307 @@ impl NativeEventHandler {
 impl Drop for NativeEventHandler {
     fn drop(&mut self) {
         if let Some(tx) = self.tx.take() {
-            tx.send(NativeEventOperation::default()).unwrap();
+            let _ = tx.send(NativeEventOperation::default());
         }
     }
 }
 
+/// The waker of the task waiting for events with [`EventStream`].
+type WakerSlot = Arc<Mutex<Option<Waker>>>;
+
+fn wake(waker: &WakerSlot) {
+    let waker = waker.lock().unwrap_or_else(PoisonError::into_inner).take();
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct EventSender {
     tx: SyncSender<(Event, NativeEventHandler)>,
+    waker: WakerSlot,
 }
 
 impl EventSender {
-    pub(crate) fn new(tx: SyncSender<(Event, NativeEventHandler)>) -> Self {
-        Self { tx }
+    fn new(tx: SyncSender<(Event, NativeEventHandler)>, waker: WakerSlot) -> Self {
+        Self { tx, waker }
     }
 
     pub(crate) fn send(&self, event: Event) -> NativeEventOperation {
+        let timeout = decision_timeout()
+            .or_else(|| kill_switch::is_set().then_some(KILL_SWITCH_DECISION_TIMEOUT));
+        self.send_with_timeout(event, timeout)
+    }
+
+    fn send_with_timeout(&self, event: Event, timeout: Option<Duration>) -> NativeEventOperation {
         let (tx, rx) = mpsc::channel();
-        let sent_data = (event, NativeEventHandler::new(tx));
+        let cancelled = Arc::<AtomicBool>::default();
+        let sent_data = (event, NativeEventHandler::new(tx, Arc::clone(&cancelled)));
+
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => {
+                return match self.tx.send(sent_data) {
+                    Ok(_) => {
+                        wake(&self.waker);
+                        rx.recv().unwrap_or_default()
+                    }
+                    Err(_) => NativeEventOperation::default(),
+                };
+            }
+        };
+
+        let deadline = Instant::now() + timeout;
+        match self.tx.try_send(sent_data) {
+            Ok(_) => wake(&self.waker),
+            Err(TrySendError::Disconnected(_)) => return NativeEventOperation::default(),
+            // The receiver has not even taken the previous event that timed out,
+            // so it is not waited for again.
+            Err(TrySendError::Full(_)) => return timed_out(&cancelled),
+        }
+        rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .unwrap_or_else(|_| timed_out(&cancelled))
+    }
+}
+
+/// Cancels the event so that the receiver drops it after it has been notified.
+fn timed_out(cancelled: &AtomicBool) -> NativeEventOperation {
+    cancelled.store(true, Ordering::SeqCst);
+    TIMED_OUT_EVENT_COUNT.fetch_add(1, Ordering::SeqCst);
+    NativeEventOperation::default()
+}
+
+/// The time to wait for [`NativeEventHandler`] to decide whether to notify an event
+/// while a kill switch is set and [`decision_timeout`] is `None`.
+/// This lets the hook procedure check the chord of the kill switch even if the receiver stalls.
+///
+/// See [`kill_switch`](crate::kill_switch) for details.
+pub const KILL_SWITCH_DECISION_TIMEOUT: Duration = Duration::from_millis(200);
+
+const NO_DECISION_TIMEOUT: u64 = u64::MAX;
+
+static DECISION_TIMEOUT: AtomicU64 = AtomicU64::new(NO_DECISION_TIMEOUT);
+
+static TIMED_OUT_EVENT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the time to wait for [`NativeEventHandler`] to decide whether to notify an event.
+/// If `None`, waits forever unless a kill switch is set
+/// (See [`KILL_SWITCH_DECISION_TIMEOUT`]). This is `None` by default, but since a kill switch
+/// is set by default, the effective default is [`KILL_SWITCH_DECISION_TIMEOUT`] (200 ms).
+///
+/// # Examples
+///
+/// ```
+/// use hookmap_core::event;
+/// assert_eq!(event::decision_timeout(), None);
+/// ```
+///
+pub fn decision_timeout() -> Option<Duration> {
+    match DECISION_TIMEOUT.load(Ordering::SeqCst) {
+        NO_DECISION_TIMEOUT => None,
+        nanos => Some(Duration::from_nanos(nanos)),
+    }
+}
+
+/// Sets the time to wait for [`NativeEventHandler`] to decide whether to notify an event.
+/// After this time has elapsed, the event is notified and cancelled.
+/// If `None`, waits forever unless a kill switch is set
+/// (See [`KILL_SWITCH_DECISION_TIMEOUT`]).
+/// A kill switch is set by default, so `None` alone still bounds the wait by 200 ms;
+/// call [`kill_switch::set(None)`](crate::kill_switch::set) as well to wait forever.
+///
+/// On Windows, the OS silently removes a hook that takes longer than `LowLevelHooksTimeout`
+/// to process an event, so the timeout should be shorter than it.
+///
+/// # Examples
+///
+/// ```
+/// use hookmap_core::event;
+/// use std::time::Duration;
+///
+/// event::set_decision_timeout(Some(Duration::from_millis(100)));
+/// ```
+///
+pub fn set_decision_timeout(timeout: Option<Duration>) {
+    let nanos = timeout.map_or(NO_DECISION_TIMEOUT, |timeout| {
+        timeout.as_nanos().min(u128::from(NO_DECISION_TIMEOUT - 1)) as u64
+    });
+    DECISION_TIMEOUT.store(nanos, Ordering::SeqCst);
+}
+
+/// Returns the number of events notified because [`decision_timeout`] has elapsed.
+///
+/// # Examples
+///
+/// ```
+/// let count = hookmap_core::event::timed_out_event_count();
+/// ```
+///
+pub fn timed_out_event_count() -> usize {
+    TIMED_OUT_EVENT_COUNT.load(Ordering::SeqCst)
+}
 
-        self.tx.send(sent_data).unwrap();
-        rx.recv().unwrap()
+impl Drop for EventSender {
+    // Lets the stream know that the hook is uninstalled.
+    fn drop(&mut self) {
+        wake(&self.waker);
     }
 }
 
 pub type EventReceiver = Receiver<(Event, NativeEventHandler)>;
 
+const BOUND: usize = 1;
+
 pub(crate) fn channel() -> (EventSender, EventReceiver) {
-    const BOUND: usize = 1;
     let (tx, rx) = mpsc::sync_channel(BOUND);
-    (EventSender::new(tx), rx)
+    (EventSender::new(tx, WakerSlot::default()), rx)
+}
+
+/// A stream of generated events.
+///
+/// This ends when the hook is uninstalled. Cancelled events are skipped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures_core::Stream;
+/// use std::pin::Pin;
+/// use std::task::{Context, Poll};
+///
+/// fn poll_event(cx: &mut Context, stream: &mut hookmap_core::event::EventStream) {
+///     if let Poll::Ready(Some((event, native_handler))) = Pin::new(stream).poll_next(cx) {
+///         println!("{:?}", event);
+///         native_handler.dispatch();
+///     }
+/// }
+/// ```
+///
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct EventStream {
+    rx: EventReceiver,
+    waker: WakerSlot,
+}
+
+#[cfg(feature = "async")]
+impl EventStream {
+    fn try_recv(&self) -> Poll<Option<(Event, NativeEventHandler)>> {
+        loop {
+            return match self.rx.try_recv() {
+                Ok((_, native_handler)) if native_handler.is_cancelled() => continue,
+                Ok(data) => Poll::Ready(Some(data)),
+                Err(TryRecvError::Disconnected) => Poll::Ready(None),
+                Err(TryRecvError::Empty) => Poll::Pending,
+            };
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl futures_core::Stream for EventStream {
+    type Item = (Event, NativeEventHandler);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Poll::Ready(data) = self.try_recv() {
+            return Poll::Ready(data);
+        }
+        *self.waker.lock().unwrap_or_else(PoisonError::into_inner) = Some(cx.waker().clone());
+
+        // An event may have been sent before the waker is registered.
+        self.try_recv()
+    }
+}
+
+#[cfg(feature = "async")]
+pub(crate) fn stream_channel() -> (EventSender, EventStream) {
+    let (tx, rx) = mpsc::sync_channel(BOUND);
+    let waker = WakerSlot::default();
+    (
+        EventSender::new(tx, Arc::clone(&waker)),
+        EventStream { rx, waker },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn event() -> Event {
+        Event::Button(ButtonEvent {
+            target: Button::A,
+            action: ButtonAction::Press,
+            injected: false,
+            is_repeat: false,
+        })
+    }
+
+    #[test]
+    fn decision_within_timeout_is_used() {
+        let (tx, rx) = channel();
+        thread::spawn(move || {
+            while let Ok((_, native_handler)) = rx.recv() {
+                assert!(!native_handler.is_cancelled());
+                native_handler.block();
+            }
+        });
+        let timeout = Some(Duration::from_secs(10));
+        assert_eq!(
+            tx.send_with_timeout(event(), timeout),
+            NativeEventOperation::Block
+        );
+        assert_eq!(
+            tx.send_with_timeout(event(), None),
+            NativeEventOperation::Block
+        );
+    }
+
+    #[test]
+    fn stale_event_is_cancelled() {
+        let (tx, rx) = channel();
+        let timeout = Some(Duration::from_millis(10));
+        assert_eq!(
+            tx.send_with_timeout(event(), timeout),
+            NativeEventOperation::Dispatch
+        );
+        let (_, native_handler) = rx.recv().unwrap();
+        assert!(native_handler.is_cancelled());
+    }
+
+    #[test]
+    fn wait_is_bounded_while_kill_switch_is_set() {
+        let (tx, rx) = channel();
+        assert!(kill_switch::is_set());
+        assert_eq!(decision_timeout(), None);
+        assert_eq!(tx.send(event()), NativeEventOperation::Dispatch);
+        assert!(rx.recv().unwrap().1.is_cancelled());
+    }
+
+    #[test]
+    fn slow_receiver_loses_block_by_default() {
+        let (tx, rx) = channel();
+        let receiver = thread::spawn(move || {
+            let (_, native_handler) = rx.recv().unwrap();
+            thread::sleep(KILL_SWITCH_DECISION_TIMEOUT * 2);
+            let is_cancelled = native_handler.is_cancelled();
+            native_handler.block();
+            is_cancelled
+        });
+        assert_eq!(decision_timeout(), None);
+        assert_eq!(tx.send(event()), NativeEventOperation::Dispatch);
+        assert!(receiver.join().unwrap());
+    }
+
+    #[test]
+    fn event_is_not_queued_after_timeout() {
+        let (tx, rx) = channel();
+        let timeout = Some(Duration::from_millis(10));
+        tx.send_with_timeout(event(), timeout);
+        assert_eq!(
+            tx.send_with_timeout(event(), timeout),
+            NativeEventOperation::Dispatch
+        );
+        assert!(rx.recv().unwrap().1.is_cancelled());
+        assert!(rx.try_recv().is_err());
+    }
 }
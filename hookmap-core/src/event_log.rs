@@ -0,0 +1,734 @@
+//! Capturing and replaying the raw event stream.
+//!
+//! An event log is a text with one event per line, preceded by a header line
+//! containing the format version.
+//!
+//! ```text
+//! hookmap-event-log 4
+//! 0 dispatch press LShift
+//! 12500 block press A
+//! 60000 block press A repeat
+//! 80000 dispatch release A injected
+//! 81000 dispatch wheel -1
+//! 85000 dispatch hwheel 0 hires 30
+//! 90000 dispatch cursor 3 -2 at 100 200 on 65537
+//! 95000 cancelled release LShift
+//! ```
+//!
+//! Each line consists of the time elapsed since the start of the capture in microseconds,
+//! the decision whether the event was blocked or dispatched, and the event.
+//! Auto-repeated button presses are followed by `repeat`,
+//! and events generated by this library end with `injected`.
+//! Cursor movements include the position and the monitor since version 2.
+//! Horizontal scrolling (`hwheel`) and rotations that are not whole notches,
+//! given in units of 1/[`WHEEL_DELTA`] after `hires`, are written since version 3.
+//! Events that were notified without waiting for the decision, because it took too long
+//! (See [`NativeEventHandler::is_cancelled`]), are written as `cancelled` since version 4.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hookmap_core::event_log::{self, EventLogWriter};
+//! use std::fs::File;
+//!
+//! let writer = EventLogWriter::new(File::create("events.log").unwrap()).unwrap();
+//! let rx = event_log::capture(hookmap_core::install_hook(), writer);
+//! while let Ok((event, native_handler)) = rx.recv() {
+//!     native_handler.dispatch();
+//! }
+//! ```
+//!
+
+use crate::button::{Button, ButtonAction};
+use crate::event::{
+    ButtonEvent, CursorEvent, Event, EventReceiver, MonitorId, NativeEventHandler,
+    NativeEventOperation, WheelAxis, WheelEvent, WHEEL_DELTA,
+};
+
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const HEADER: &str = "hookmap-event-log";
+
+/// The version of the event log format written by [`EventLogWriter`].
+/// [`EventLogReader`] also reads the logs of the earlier versions.
+pub const FORMAT_VERSION: u32 = 4;
+
+impl Display for Event {
+    /// Formats the event as in the event log, e.g. `press A`, `wheel -1`,
+    /// `hwheel 0 hires 30` or `cursor 3 -2 at 100 200 on 65537`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let injected = match self {
+            Event::Button(event) => {
+                let action = match event.action {
+                    ButtonAction::Press => "press",
+                    ButtonAction::Release => "release",
+                };
+                write!(f, "{} {}", action, event.target)?;
+                if event.is_repeat {
+                    write!(f, " repeat")?;
+                }
+                event.injected
+            }
+            Event::Wheel(event) => {
+                let kind = match event.axis {
+                    WheelAxis::Vertical => "wheel",
+                    WheelAxis::Horizontal => "hwheel",
+                };
+                write!(f, "{} {}", kind, event.delta)?;
+                if event.hi_res_delta != event.delta * WHEEL_DELTA {
+                    write!(f, " hires {}", event.hi_res_delta)?;
+                }
+                event.injected
+            }
+            Event::Cursor(event) => {
+                let (dx, dy) = event.delta;
+                let (x, y) = event.position;
+                write!(
+                    f,
+                    "cursor {} {} at {} {} on {}",
+                    dx, dy, x, y, event.monitor.0
+                )?;
+                event.injected
+            }
+        };
+        if injected {
+            write!(f, " injected")?;
+        }
+        Ok(())
+    }
+}
+
+/// An error returned when parsing an [`Event`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseEventError(String);
+
+impl Display for ParseEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseEventError {}
+
+fn parse_number<T: FromStr>(token: Option<&str>) -> Result<T, ParseEventError> {
+    let token = token.ok_or_else(|| ParseEventError("missing number".to_owned()))?;
+    token
+        .parse()
+        .map_err(|_| ParseEventError(format!("invalid number: `{}`", token)))
+}
+
+impl FromStr for Event {
+    type Err = ParseEventError;
+
+    /// Parses the representation written by [`Display`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap_core::button::{Button, ButtonAction};
+    /// use hookmap_core::event::{ButtonEvent, Event};
+    ///
+    /// let event = Event::Button(ButtonEvent {
+    ///     target: Button::A,
+    ///     action: ButtonAction::Press,
+    ///     injected: true,
+    ///     is_repeat: false,
+    /// });
+    /// assert_eq!("press A injected".parse(), Ok(event));
+    /// ```
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s.split_whitespace();
+        let kind = tokens
+            .next()
+            .ok_or_else(|| ParseEventError("missing event kind".to_owned()))?;
+
+        let mut event = match kind {
+            "press" | "release" => {
+                let name = tokens
+                    .next()
+                    .ok_or_else(|| ParseEventError("missing button".to_owned()))?;
+                let target: Button = name
+                    .parse()
+                    .map_err(|e| ParseEventError(format!("{}", e)))?;
+                let action = if kind == "press" {
+                    ButtonAction::Press
+                } else {
+                    ButtonAction::Release
+                };
+                Event::Button(ButtonEvent {
+                    target,
+                    action,
+                    injected: false,
+                    is_repeat: false,
+                })
+            }
+            "wheel" | "hwheel" => {
+                let delta: i32 = parse_number(tokens.next())?;
+                let hi_res_delta = delta.checked_mul(WHEEL_DELTA).ok_or_else(|| {
+                    ParseEventError(format!("wheel delta out of range: `{}`", delta))
+                })?;
+                Event::Wheel(WheelEvent {
+                    delta,
+                    hi_res_delta,
+                    axis: if kind == "wheel" {
+                        WheelAxis::Vertical
+                    } else {
+                        WheelAxis::Horizontal
+                    },
+                    injected: false,
+                })
+            }
+            "cursor" => Event::Cursor(CursorEvent {
+                delta: (parse_number(tokens.next())?, parse_number(tokens.next())?),
+                position: (0, 0),
+                monitor: MonitorId::default(),
+                injected: false,
+            }),
+            _ => return Err(ParseEventError(format!("unknown event kind: `{}`", kind))),
+        };
+
+        let mut token = tokens.next();
+        if let Event::Cursor(event) = &mut event {
+            if token == Some("at") {
+                event.position = (parse_number(tokens.next())?, parse_number(tokens.next())?);
+                token = tokens.next();
+            }
+            if token == Some("on") {
+                event.monitor = MonitorId(parse_number(tokens.next())?);
+                token = tokens.next();
+            }
+        }
+        if let (Event::Wheel(event), Some("hires")) = (&mut event, token) {
+            event.hi_res_delta = parse_number(tokens.next())?;
+            token = tokens.next();
+        }
+        if let (Event::Button(event), Some("repeat")) = (&mut event, token) {
+            event.is_repeat = true;
+            token = tokens.next();
+        }
+        if token == Some("injected") {
+            match &mut event {
+                Event::Button(event) => event.injected = true,
+                Event::Wheel(event) => event.injected = true,
+                Event::Cursor(event) => event.injected = true,
+            }
+            token = tokens.next();
+        }
+        match token {
+            None => Ok(event),
+            Some(token) => Err(ParseEventError(format!("unexpected token: `{}`", token))),
+        }
+    }
+}
+
+/// An event and the decision made for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LogEntry {
+    /// Time elapsed since the start of the capture.
+    pub time: Duration,
+
+    /// Whether the event was blocked or dispatched.
+    /// This is [`NativeEventOperation::Dispatch`] if the event was cancelled.
+    pub operation: NativeEventOperation,
+
+    /// Whether the event was notified without waiting for the decision.
+    /// A replayed event is cancelled if this is `true`.
+    pub cancelled: bool,
+
+    /// The captured event.
+    pub event: Event,
+}
+
+impl Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operation = match self.operation {
+            _ if self.cancelled => "cancelled",
+            NativeEventOperation::Block => "block",
+            NativeEventOperation::Dispatch => "dispatch",
+        };
+        write!(f, "{} {} {}", self.time.as_micros(), operation, self.event)
+    }
+}
+
+impl FromStr for LogEntry {
+    type Err = ParseEventError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim_start();
+        let (time, s) = s.split_once(' ').unwrap_or((s, ""));
+        let time = Duration::from_micros(parse_number(Some(time))?);
+
+        let s = s.trim_start();
+        let (operation, event) = s.split_once(' ').unwrap_or((s, ""));
+        let (operation, cancelled) = match operation {
+            "block" => (NativeEventOperation::Block, false),
+            "dispatch" => (NativeEventOperation::Dispatch, false),
+            "cancelled" => (NativeEventOperation::Dispatch, true),
+            _ => {
+                return Err(ParseEventError(format!(
+                    "invalid decision: `{}`",
+                    operation
+                )))
+            }
+        };
+
+        Ok(LogEntry {
+            time,
+            operation,
+            cancelled,
+            event: event.parse()?,
+        })
+    }
+}
+
+/// An error returned when reading an event log fails.
+#[derive(Debug)]
+pub enum EventLogError {
+    Io(io::Error),
+
+    /// The log is malformed at the 1-based `line`.
+    Parse {
+        line: usize,
+        error: ParseEventError,
+    },
+
+    /// The log is written in a format version that cannot be read.
+    UnsupportedVersion(String),
+}
+
+impl Display for EventLogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventLogError::Io(e) => write!(f, "{}", e),
+            EventLogError::Parse { line, error } => write!(f, "line {}: {}", line, error),
+            EventLogError::UnsupportedVersion(header) => {
+                write!(f, "unsupported event log header: `{}`", header)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EventLogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EventLogError::Io(e) => Some(e),
+            EventLogError::Parse { error, .. } => Some(error),
+            EventLogError::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for EventLogError {
+    fn from(e: io::Error) -> Self {
+        EventLogError::Io(e)
+    }
+}
+
+/// Writes an event log.
+#[derive(Debug)]
+pub struct EventLogWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> EventLogWriter<W> {
+    /// Creates a new writer and writes the header.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writeln!(writer, "{} {}", HEADER, FORMAT_VERSION)?;
+        Ok(Self { writer })
+    }
+
+    pub fn write(&mut self, entry: &LogEntry) -> io::Result<()> {
+        writeln!(self.writer, "{}", entry)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads an event log written by [`EventLogWriter`].
+///
+/// # Examples
+///
+/// ```
+/// use hookmap_core::event_log::EventLogReader;
+///
+/// let log = "hookmap-event-log 1\n0 block press A\n1000 dispatch wheel 1\n";
+/// let reader = EventLogReader::new(log.as_bytes()).unwrap();
+/// let entries = reader.collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(entries.len(), 2);
+/// ```
+///
+#[derive(Debug)]
+pub struct EventLogReader<R: BufRead> {
+    lines: io::Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> EventLogReader<R> {
+    /// Creates a new reader and checks the header.
+    pub fn new(reader: R) -> Result<Self, EventLogError> {
+        let mut lines = reader.lines();
+        let header = lines.next().transpose()?.unwrap_or_default();
+        let version = header
+            .trim()
+            .strip_prefix(HEADER)
+            .and_then(|version| version.trim().parse::<u32>().ok());
+        if !matches!(version, Some(1..=FORMAT_VERSION)) {
+            return Err(EventLogError::UnsupportedVersion(header));
+        }
+        Ok(Self { lines, line: 1 })
+    }
+}
+
+impl<R: BufRead> Iterator for EventLogReader<R> {
+    type Item = Result<LogEntry, EventLogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let text = match self.lines.next()? {
+                Ok(text) => text,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line += 1;
+            if text.trim().is_empty() {
+                continue;
+            }
+            let line = self.line;
+            return Some(
+                text.parse()
+                    .map_err(|error| EventLogError::Parse { line, error }),
+            );
+        }
+    }
+}
+
+/// Writes the events received from `receiver` and the decisions made for them to `writer`,
+/// and returns the receiver to which the events are forwarded.
+///
+/// Cancelled events (See [`NativeEventHandler::is_cancelled`]) are written as `cancelled`
+/// and dispatched, since other programs have already been notified of them.
+/// Writing stops if an error occurs while writing.
+pub fn capture<W>(receiver: EventReceiver, mut writer: EventLogWriter<W>) -> EventReceiver
+where
+    W: Write + Send + 'static,
+{
+    let (tx, rx) = mpsc::sync_channel(0);
+    let start = Instant::now();
+    thread::spawn(move || {
+        let mut is_writable = true;
+        while let Ok((event, native_handler)) = receiver.recv() {
+            let time = start.elapsed();
+            let cancelled = native_handler.cancellation();
+            let operation = forward(&tx, event, Arc::clone(&cancelled));
+            native_handler.handle(operation);
+            let cancelled = cancelled.load(Ordering::SeqCst);
+
+            if is_writable {
+                is_writable = writer
+                    .write(&LogEntry {
+                        time,
+                        operation: decision(operation, cancelled),
+                        cancelled,
+                        event,
+                    })
+                    .and_then(|_| writer.flush())
+                    .is_ok();
+            }
+        }
+    });
+    rx
+}
+
+/// Returns the decision that took effect. A cancelled event has been dispatched.
+fn decision(operation: NativeEventOperation, cancelled: bool) -> NativeEventOperation {
+    if cancelled {
+        NativeEventOperation::Dispatch
+    } else {
+        operation
+    }
+}
+
+/// Sends the event and waits for the decision.
+fn forward(
+    tx: &SyncSender<(Event, NativeEventHandler)>,
+    event: Event,
+    cancelled: Arc<AtomicBool>,
+) -> NativeEventOperation {
+    let (decision_tx, decision_rx) = mpsc::channel();
+    if tx
+        .send((event, NativeEventHandler::new(decision_tx, cancelled)))
+        .is_err()
+    {
+        return NativeEventOperation::default();
+    }
+    decision_rx.recv().unwrap_or_default()
+}
+
+/// A replay started by [`replay`].
+#[derive(Debug)]
+pub struct Replay {
+    join_handle: JoinHandle<Vec<NativeEventOperation>>,
+}
+
+impl Replay {
+    /// Waits until all the events are handled, and returns the decisions made for them.
+    pub fn join(self) -> Vec<NativeEventOperation> {
+        self.join_handle.join().unwrap_or_default()
+    }
+}
+
+/// Sends the logged events to the returned receiver, one after another,
+/// without installing a hook. The receiver is disconnected when all the events are handled.
+///
+/// The events are sent as fast as they are handled, regardless of [`LogEntry::time`].
+/// The events whose [`LogEntry::cancelled`] is `true` are cancelled, and their decisions are
+/// [`NativeEventOperation::Dispatch`]. The decisions can be compared with [`LogEntry::operation`].
+///
+/// # Examples
+///
+/// ```
+/// use hookmap_core::event_log::{self, EventLogReader};
+/// use hookmap_core::event::NativeEventOperation;
+///
+/// let log = "hookmap-event-log 1\n0 block press A\n1000 dispatch release A\n";
+/// let entries: Vec<_> = EventLogReader::new(log.as_bytes())
+///     .unwrap()
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+///
+/// let (rx, replay) = event_log::replay(entries.clone());
+/// while let Ok((event, native_handler)) = rx.recv() {
+///     native_handler.dispatch();
+/// }
+/// let decisions = replay.join();
+/// assert_eq!(decisions, vec![NativeEventOperation::Dispatch; 2]);
+/// ```
+///
+pub fn replay(entries: impl IntoIterator<Item = LogEntry>) -> (EventReceiver, Replay) {
+    let entries: Vec<_> = entries.into_iter().collect();
+    let (tx, rx) = mpsc::sync_channel(0);
+    let join_handle = thread::spawn(move || {
+        entries
+            .into_iter()
+            .map(|entry| {
+                let cancelled = Arc::new(AtomicBool::new(entry.cancelled));
+                decision(forward(&tx, entry.event, cancelled), entry.cancelled)
+            })
+            .collect()
+    });
+    (rx, Replay { join_handle })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<LogEntry> {
+        vec![
+            LogEntry {
+                time: Duration::ZERO,
+                operation: NativeEventOperation::Dispatch,
+                cancelled: false,
+                event: Event::Button(ButtonEvent {
+                    target: Button::LShift,
+                    action: ButtonAction::Press,
+                    injected: false,
+                    is_repeat: true,
+                }),
+            },
+            LogEntry {
+                time: Duration::from_micros(12500),
+                operation: NativeEventOperation::Block,
+                cancelled: false,
+                event: Event::Wheel(WheelEvent {
+                    delta: -1,
+                    hi_res_delta: -120,
+                    axis: WheelAxis::Vertical,
+                    injected: true,
+                }),
+            },
+            LogEntry {
+                time: Duration::from_millis(85),
+                operation: NativeEventOperation::Block,
+                cancelled: false,
+                event: Event::Wheel(WheelEvent {
+                    delta: 0,
+                    hi_res_delta: 30,
+                    axis: WheelAxis::Horizontal,
+                    injected: false,
+                }),
+            },
+            LogEntry {
+                time: Duration::from_millis(90),
+                operation: NativeEventOperation::Dispatch,
+                cancelled: false,
+                event: Event::Cursor(CursorEvent {
+                    delta: (3, -2),
+                    position: (100, 200),
+                    monitor: MonitorId(65537),
+                    injected: false,
+                }),
+            },
+            LogEntry {
+                time: Duration::from_millis(95),
+                operation: NativeEventOperation::Dispatch,
+                cancelled: true,
+                event: Event::Button(ButtonEvent {
+                    target: Button::LShift,
+                    action: ButtonAction::Release,
+                    injected: false,
+                    is_repeat: false,
+                }),
+            },
+        ]
+    }
+
+    #[test]
+    fn event_log_round_trips() {
+        let mut writer = EventLogWriter::new(Vec::new()).unwrap();
+        entries().iter().for_each(|e| writer.write(e).unwrap());
+        let log = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            log,
+            "hookmap-event-log 4\n\
+             0 dispatch press LShift repeat\n\
+             12500 block wheel -1 injected\n\
+             85000 block hwheel 0 hires 30\n\
+             90000 dispatch cursor 3 -2 at 100 200 on 65537\n\
+             95000 cancelled release LShift\n"
+        );
+
+        let reader = EventLogReader::new(log.as_bytes()).unwrap();
+        assert_eq!(reader.collect::<Result<Vec<_>, _>>().unwrap(), entries());
+    }
+
+    #[test]
+    fn version_1_event_log_is_read() {
+        let log = "hookmap-event-log 1\n0 dispatch cursor 3 -2\n";
+        let mut reader = EventLogReader::new(log.as_bytes()).unwrap();
+        let event = reader.next().unwrap().unwrap().event;
+        assert_eq!(
+            event,
+            Event::Cursor(CursorEvent {
+                delta: (3, -2),
+                position: (0, 0),
+                monitor: MonitorId::default(),
+                injected: false,
+            })
+        );
+    }
+
+    #[test]
+    fn malformed_event_log_is_rejected() {
+        assert!(matches!(
+            EventLogReader::new("hookmap-event-log 5\n".as_bytes()),
+            Err(EventLogError::UnsupportedVersion(_))
+        ));
+
+        let log = "hookmap-event-log 1\n0 block press A\n\n5 pass press A\n";
+        let mut reader = EventLogReader::new(log.as_bytes()).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(
+            reader.next().unwrap(),
+            Err(EventLogError::Parse { line: 4, .. })
+        ));
+
+        assert!("press Foo".parse::<Event>().is_err());
+        assert!("cursor 1".parse::<Event>().is_err());
+        assert!("cursor 1 2 at 3".parse::<Event>().is_err());
+        assert!("wheel 1 injected 2".parse::<Event>().is_err());
+        assert!("wheel 1 repeat".parse::<Event>().is_err());
+        assert!("hwheel 1 hires".parse::<Event>().is_err());
+        assert!(matches!(
+            "0 dispatch wheel 99999999".parse::<LogEntry>(),
+            Err(ParseEventError(_))
+        ));
+        assert!("press A hires 30".parse::<Event>().is_err());
+        assert!("press A injected repeat".parse::<Event>().is_err());
+    }
+
+    #[test]
+    fn replay_collects_decisions() {
+        let (rx, replay) = replay(entries());
+        thread::spawn(move || {
+            while let Ok((event, native_handler)) = rx.recv() {
+                match event {
+                    Event::Wheel(_) => native_handler.block(),
+                    _ => native_handler.dispatch(),
+                }
+            }
+        });
+        let decisions = replay.join();
+        let expected: Vec<_> = entries().iter().map(|e| e.operation).collect();
+        assert_eq!(decisions, expected);
+    }
+
+    struct ChannelWriter(mpsc::Sender<Vec<u8>>);
+
+    impl Write for ChannelWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let _ = self.0.send(buf.to_vec());
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Captures `event` whose decision is to block it, and returns the logged entries.
+    fn capture_blocked(event: Event, cancelled: bool) -> Vec<LogEntry> {
+        let (source_tx, source_rx) = mpsc::sync_channel(0);
+        let (log_tx, log_rx) = mpsc::channel();
+
+        let writer = EventLogWriter::new(ChannelWriter(log_tx)).unwrap();
+        let rx = capture(source_rx, writer);
+        thread::spawn(move || {
+            if let Ok((_, native_handler)) = rx.recv() {
+                native_handler.block();
+            }
+        });
+
+        assert_eq!(
+            forward(&source_tx, event, Arc::new(AtomicBool::new(cancelled))),
+            NativeEventOperation::Block
+        );
+        drop(source_tx);
+
+        let log: Vec<u8> = log_rx.iter().flatten().collect();
+        EventLogReader::new(log.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn capture_writes_decisions() {
+        let event = entries()[0].event;
+        let entries = capture_blocked(event, false);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].operation, NativeEventOperation::Block);
+        assert!(!entries[0].cancelled);
+        assert_eq!(entries[0].event, event);
+    }
+
+    #[test]
+    fn cancelled_event_is_written_as_dispatched() {
+        let entries = capture_blocked(entries()[0].event, true);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].operation, NativeEventOperation::Dispatch);
+        assert!(entries[0].cancelled);
+    }
+}
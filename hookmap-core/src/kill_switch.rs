@@ -0,0 +1,247 @@
+//! An escape hatch that stops delivering events.
+//!
+//! When all buttons of the chord are physically held down for a while, the kill switch is
+//! triggered. Buttons pressed by this library do not count.
+//! This is checked in the hook procedure before events are passed to the [`EventReceiver`].
+//! While a kill switch is set, the hook procedure does not wait for the decision on an event
+//! longer than [`KILL_SWITCH_DECISION_TIMEOUT`] even if no [`decision_timeout`] is set,
+//! so that the chord is still checked if the program receiving events blocks or stalls.
+//! Since a kill switch is set by default, this makes 200 ms the effective default limit:
+//! a receiver that takes longer to decide loses the block, and the event is notified
+//! and cancelled. Call [`set`] with `None` to wait for the decision forever.
+//!
+//! When the kill switch is triggered,
+//!
+//! * all events are notified to other programs and are no longer passed to the [`EventReceiver`],
+//! * buttons pressed by this library are released (See [`release_injected_buttons`]),
+//! * the handler set by [`set_handler`] is called in another thread.
+//!
+//! Calling [`reset`] resumes passing events to the [`EventReceiver`].
+//!
+//! By default, the chord is `Ctrl+Alt+Shift+Esc` and it must be held for one second.
+//!
+//! [`EventReceiver`]: crate::event::EventReceiver
+//! [`KILL_SWITCH_DECISION_TIMEOUT`]: crate::event::KILL_SWITCH_DECISION_TIMEOUT
+//! [`decision_timeout`]: crate::event::decision_timeout
+//! [`release_injected_buttons`]: crate::release_injected_buttons
+//!
+//! # Examples
+//!
+//! ```
+//! use hookmap_core::{button::Button, kill_switch::{self, KillSwitch}};
+//! use std::time::Duration;
+//!
+//! kill_switch::set(Some(KillSwitch::new(
+//!     [Button::Ctrl, Button::Alt, Button::End],
+//!     Duration::from_millis(500),
+//! )));
+//! kill_switch::set_handler(|| eprintln!("All hooks are disabled."));
+//! ```
+//!
+
+use crate::button::Button;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+/// A chord that triggers the kill switch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KillSwitch {
+    chord: Vec<Button>,
+    hold: Duration,
+}
+
+impl KillSwitch {
+    /// Creates a new instance of [`KillSwitch`].
+    /// The kill switch is triggered when all buttons of `chord` are held down for `hold`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap_core::{button::Button, kill_switch::KillSwitch};
+    /// use std::time::Duration;
+    ///
+    /// let kill_switch = KillSwitch::new([Button::Ctrl, Button::Esc], Duration::from_secs(2));
+    /// ```
+    ///
+    pub fn new(chord: impl IntoIterator<Item = Button>, hold: Duration) -> Self {
+        Self {
+            chord: chord.into_iter().collect(),
+            hold,
+        }
+    }
+
+    /// Returns the buttons of the chord.
+    pub fn chord(&self) -> &[Button] {
+        &self.chord
+    }
+
+    /// Returns the time for which the chord must be held down.
+    pub fn hold(&self) -> Duration {
+        self.hold
+    }
+}
+
+impl Default for KillSwitch {
+    fn default() -> Self {
+        Self::new(
+            [Button::Ctrl, Button::Alt, Button::Shift, Button::Esc],
+            Duration::from_secs(1),
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct Detector {
+    held_since: Option<Instant>,
+}
+
+impl Detector {
+    fn update(&mut self, kill_switch: &KillSwitch, is_pressed: bool, now: Instant) -> bool {
+        if !is_pressed || kill_switch.chord.is_empty() {
+            self.held_since = None;
+            return false;
+        }
+        let held_since = *self.held_since.get_or_insert(now);
+        if now.duration_since(held_since) < kill_switch.hold {
+            return false;
+        }
+        self.held_since = None;
+        true
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    kill_switch: Option<KillSwitch>,
+    detector: Detector,
+}
+
+static STATE: Lazy<Mutex<State>> = Lazy::new(|| {
+    Mutex::new(State {
+        kill_switch: Some(KillSwitch::default()),
+        detector: Detector::default(),
+    })
+});
+
+static IS_TRIGGERED: AtomicBool = AtomicBool::new(false);
+
+type Handler = Arc<dyn Fn() + Send + Sync>;
+
+static HANDLER: Lazy<Mutex<Option<Handler>>> = Lazy::new(Mutex::default);
+
+fn state() -> MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Sets the kill switch. If `None`, the kill switch is disabled.
+///
+/// # Examples
+///
+/// ```
+/// use hookmap_core::kill_switch;
+/// kill_switch::set(None);
+/// ```
+///
+pub fn set(kill_switch: Option<KillSwitch>) {
+    let mut state = state();
+    state.kill_switch = kill_switch;
+    state.detector = Detector::default();
+}
+
+/// Sets the handler called when the kill switch is triggered.
+///
+/// # Examples
+///
+/// ```
+/// use hookmap_core::kill_switch;
+/// kill_switch::set_handler(|| eprintln!("The kill switch is triggered."));
+/// ```
+///
+pub fn set_handler<F: Fn() + Send + Sync + 'static>(handler: F) {
+    *HANDLER.lock().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(handler));
+}
+
+/// Returns `true` if a kill switch is set.
+pub(crate) fn is_set() -> bool {
+    state().kill_switch.is_some()
+}
+
+/// Returns `true` if the kill switch has been triggered and not reset yet.
+pub fn is_triggered() -> bool {
+    IS_TRIGGERED.load(Ordering::SeqCst)
+}
+
+/// Resumes passing events to the [`EventReceiver`](crate::event::EventReceiver)
+/// after the kill switch is triggered.
+pub fn reset() {
+    IS_TRIGGERED.store(false, Ordering::SeqCst);
+}
+
+/// Checks whether the chord has been held down long enough,
+/// and returns `true` if the kill switch is triggered by this call.
+pub(crate) fn check(is_pressed: impl Fn(Button) -> bool) -> bool {
+    let mut state = state();
+    let State {
+        kill_switch,
+        detector,
+    } = &mut *state;
+    let kill_switch = match kill_switch {
+        Some(kill_switch) => kill_switch,
+        None => return false,
+    };
+
+    let is_chord_pressed = kill_switch.chord.iter().all(|&button| is_pressed(button));
+    if !detector.update(kill_switch, is_chord_pressed, Instant::now()) {
+        return false;
+    }
+    !IS_TRIGGERED.swap(true, Ordering::SeqCst)
+}
+
+pub(crate) fn call_handler() {
+    let handler = HANDLER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    if let Some(handler) = handler {
+        handler();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detector_triggers_after_chord_is_held() {
+        let kill_switch = KillSwitch::new([Button::A, Button::B], Duration::from_secs(1));
+        let mut detector = Detector::default();
+        let start = Instant::now();
+
+        assert!(!detector.update(&kill_switch, true, start));
+        assert!(!detector.update(&kill_switch, true, start + Duration::from_millis(500)));
+        assert!(detector.update(&kill_switch, true, start + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn detector_restarts_when_chord_is_released() {
+        let kill_switch = KillSwitch::new([Button::A], Duration::from_secs(1));
+        let mut detector = Detector::default();
+        let start = Instant::now();
+
+        assert!(!detector.update(&kill_switch, true, start));
+        assert!(!detector.update(&kill_switch, false, start + Duration::from_millis(500)));
+        assert!(!detector.update(&kill_switch, true, start + Duration::from_secs(1)));
+        assert!(detector.update(&kill_switch, true, start + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn detector_ignores_empty_chord() {
+        let kill_switch = KillSwitch::new([], Duration::ZERO);
+        let mut detector = Detector::default();
+        assert!(!detector.update(&kill_switch, true, Instant::now()));
+    }
+}
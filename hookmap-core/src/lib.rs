@@ -8,13 +8,24 @@
 //!
 //! * `us-keyboard-layout` (default): Use US keyboard layout. This changes the [`Button`] variant.
 //! * `japanese-keyboard-layout`: Use Japanese keyboard layout. This changes the [`Button`] variant.
+//! * `async`: Enable APIs that receive events with [`Future`](std::future::Future) and `Stream`.
 //!
 //! [`Button`]: button::Button
 //!
 
 pub mod button;
 pub mod event;
+pub mod event_log;
+pub mod kill_switch;
+pub mod output;
+pub mod window;
 
 mod sys;
 
-pub use sys::{install_hook, mouse, uninstall_hook};
+pub use sys::{
+    catch_unwind, install_hook, keyboard, mouse, release_injected_buttons,
+    release_injected_buttons_on_panic, set_emergency_release_chord, uninstall_hook,
+};
+
+#[cfg(feature = "async")]
+pub use sys::install_hook_async;
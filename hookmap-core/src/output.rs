@@ -0,0 +1,136 @@
+//! Redirecting the inputs sent by this library.
+//!
+//! Inputs sent by functions such as [`Button::press`] are usually passed to the OS.
+//! While they are redirected with [`redirect`], they are passed to an [`OutputBackend`] instead,
+//! and the state of buttons is not changed by them. This allows checking the inputs
+//! without affecting the desktop, e.g. while replaying an event log.
+//!
+//! # Examples
+//!
+//! ```
+//! use hookmap_core::button::{Button, ButtonAction};
+//! use hookmap_core::output::{self, Output, OutputLog};
+//!
+//! let log = OutputLog::new();
+//! let redirect = output::redirect(log.clone());
+//! Button::A.click();
+//! drop(redirect);
+//!
+//! assert_eq!(
+//!     log.take(),
+//!     vec![
+//!         Output::Button(Button::A, ButtonAction::Press),
+//!         Output::Button(Button::A, ButtonAction::Release),
+//!     ]
+//! );
+//! ```
+//!
+
+use crate::button::{Button, ButtonAction};
+use crate::event::WheelAxis;
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+
+use once_cell::sync::Lazy;
+
+/// An input sent by this library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Output {
+    /// A button is pressed or released.
+    /// Generic modifiers such as [`Button::Shift`] are sent as both sides of them.
+    Button(Button, ButtonAction),
+
+    /// A character is typed as Unicode input.
+    Unicode(char),
+
+    /// The mouse cursor is moved to the coordinates `(x, y)`.
+    MoveTo(i32, i32),
+
+    /// The mouse cursor is moved by `(dx, dy)`.
+    MoveBy(i32, i32),
+
+    /// The mouse wheel is rotated along the axis in units of 1/[`WHEEL_DELTA`] of a notch.
+    ///
+    /// [`WHEEL_DELTA`]: crate::event::WHEEL_DELTA
+    Wheel(WheelAxis, i32),
+}
+
+/// Receives the inputs redirected by [`redirect`].
+pub trait OutputBackend: Send + Sync {
+    /// Receives an input instead of the OS.
+    fn send(&self, output: Output);
+}
+
+/// A backend that records the inputs.
+#[derive(Debug, Default, Clone)]
+pub struct OutputLog(Arc<Mutex<Vec<Output>>>);
+
+impl OutputLog {
+    /// Creates a new instance of [`OutputLog`] without any inputs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the recorded inputs out of the log, in the order in which they were sent.
+    pub fn take(&self) -> Vec<Output> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl OutputBackend for OutputLog {
+    fn send(&self, output: Output) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(output);
+    }
+}
+
+static BACKEND: Lazy<RwLock<Option<Arc<dyn OutputBackend>>>> = Lazy::new(RwLock::default);
+
+// Held while the inputs are redirected, so that only one backend receives them at a time.
+static REDIRECTION: Lazy<Mutex<()>> = Lazy::new(Mutex::default);
+
+/// A guard returned by [`redirect`]. The inputs are passed to the OS again when this is dropped.
+#[must_use = "the inputs are passed to the OS again when this is dropped"]
+pub struct Redirect {
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl std::fmt::Debug for Redirect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Redirect")
+    }
+}
+
+impl Drop for Redirect {
+    fn drop(&mut self) {
+        *BACKEND.write().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+/// Passes the inputs sent by this library to `backend` until the returned guard is dropped.
+///
+/// If the inputs are already redirected, this waits until the previous guard is dropped.
+pub fn redirect(backend: impl OutputBackend + 'static) -> Redirect {
+    let lock = REDIRECTION.lock().unwrap_or_else(PoisonError::into_inner);
+    *BACKEND.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(backend));
+    Redirect { _lock: lock }
+}
+
+/// Returns the backend if the inputs are redirected.
+pub(crate) fn backend() -> Option<Arc<dyn OutputBackend>> {
+    BACKEND
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Passes `output` to the backend if the inputs are redirected, otherwise calls `send`.
+#[inline]
+pub(crate) fn send_or_redirect(output: Output, send: impl FnOnce()) {
+    match backend() {
+        Some(backend) => backend.send(output),
+        None => send(),
+    }
+}
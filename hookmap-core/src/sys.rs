@@ -2,4 +2,16 @@
 mod windows;
 
 #[cfg(target_os = "windows")]
-pub use self::windows::{install_hook, mouse, uninstall_hook};
+pub use self::windows::{
+    catch_unwind, foreground_window, install_hook, keyboard, mouse, release_injected_buttons,
+    release_injected_buttons_on_panic, set_emergency_release_chord, uninstall_hook,
+};
+
+#[cfg(target_os = "linux")]
+mod x11;
+
+#[cfg(target_os = "linux")]
+pub use self::x11::foreground_window;
+
+#[cfg(all(target_os = "windows", feature = "async"))]
+pub use self::windows::install_hook_async;
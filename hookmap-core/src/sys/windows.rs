@@ -1,6 +1,7 @@
 mod hook;
 mod input;
 mod vkcode;
+mod window;
 
 use hook::HookHandler;
 use input::Input;
@@ -8,11 +9,20 @@ use windows::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
 use windows::Win32::UI::WindowsAndMessaging::HHOOK;
 
 use crate::button::{Button, ButtonAction};
-use crate::event::{self, EventReceiver, NativeEventOperation};
+use crate::event::{self, EventReceiver, EventSender, NativeEventOperation};
+use crate::output::{self, Output};
 
+#[cfg(feature = "async")]
+use crate::event::EventStream;
+
+use std::cell::Cell;
+use std::panic::{self, UnwindSafe};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, Once, PoisonError};
+use std::thread;
 
 use once_cell::sync::Lazy;
+use windows::Win32::UI::Input::KeyboardAndMouse;
 use windows::Win32::UI::{HiDpi, WindowsAndMessaging};
 
 const SHOULD_BE_IGNORED_FLAG: usize = 0x1;
@@ -54,29 +64,202 @@ impl ButtonState {
 
 static BUTTON_STATE: ButtonState = ButtonState::new();
 
+// The state of buttons changed only by inputs not generated by this library.
+static PHYSICAL_BUTTON_STATE: ButtonState = ButtonState::new();
+
 static INPUT: Lazy<Input> = Lazy::new(Input::new);
 
+// Buttons pressed by this library and not released yet, in the order in which they were pressed.
+static INJECTED_PRESSED_BUTTONS: Lazy<Mutex<Vec<Button>>> = Lazy::new(Mutex::default);
+
+static EMERGENCY_RELEASE_CHORD: Lazy<Mutex<Vec<Button>>> = Lazy::new(Mutex::default);
+
+// The lock must not be poisoned, since it is also acquired in the panic hook.
+fn injected_pressed_buttons() -> MutexGuard<'static, Vec<Button>> {
+    INJECTED_PRESSED_BUTTONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
 #[inline]
-fn send_input(button: Button, action: ButtonAction, recursive: bool, assume: fn(Button)) {
-    let left_and_right_modifier = match button {
+fn left_and_right_modifier(button: Button) -> Option<(Button, Button)> {
+    match button {
         Button::Shift => Some((Button::LShift, Button::RShift)),
         Button::Ctrl => Some((Button::LCtrl, Button::RCtrl)),
         Button::Alt => Some((Button::LAlt, Button::RAlt)),
         Button::Super => Some((Button::LSuper, Button::RSuper)),
         _ => None,
-    };
-    if let Some((left, right)) = left_and_right_modifier {
+    }
+}
+
+#[inline]
+fn track_injected_input(button: Button, action: ButtonAction) {
+    let mut pressed = injected_pressed_buttons();
+    pressed.retain(|&pressed| pressed != button);
+    if action == ButtonAction::Press {
+        pressed.push(button);
+    }
+}
+
+#[inline]
+fn button_input(button: Button, action: ButtonAction, recursive: bool) {
+    INPUT.button_input(button, action, recursive);
+    track_injected_input(button, action);
+}
+
+#[inline]
+fn send_input(button: Button, action: ButtonAction, recursive: bool, assume: fn(Button)) {
+    // Redirected inputs do not change the state of buttons.
+    if let Some(backend) = output::backend() {
+        match left_and_right_modifier(button) {
+            Some((left, right)) => {
+                backend.send(Output::Button(left, action));
+                backend.send(Output::Button(right, action));
+            }
+            None => backend.send(Output::Button(button, action)),
+        }
+        return;
+    }
+    if let Some((left, right)) = left_and_right_modifier(button) {
         assume(left);
         assume(right);
         assume(button);
-        INPUT.button_input(left, action, recursive);
-        INPUT.button_input(right, action, recursive);
+        button_input(left, action, recursive);
+        button_input(right, action, recursive);
     } else {
         assume(button);
-        INPUT.button_input(button, action, recursive);
+        button_input(button, action, recursive);
     }
 }
 
+// Generic modifiers such as `Button::Shift` are pressed if either side of them is pressed.
+fn is_either_pressed(button: Button) -> bool {
+    match left_and_right_modifier(button) {
+        Some((left, right)) => left.is_pressed() || right.is_pressed(),
+        None => button.is_pressed(),
+    }
+}
+
+fn is_emergency_release_chord_pressed(target: Button) -> bool {
+    let chord = EMERGENCY_RELEASE_CHORD
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let contains = |button: Button, target: Button| {
+        button == target
+            || matches!(
+                left_and_right_modifier(button),
+                Some((left, right)) if left == target || right == target
+            )
+    };
+
+    chord.iter().any(|&button| contains(button, target))
+        && chord.iter().all(|&button| is_either_pressed(button))
+}
+
+/// Releases all buttons that have been pressed by this library and not released yet.
+///
+/// This is called automatically when [`uninstall_hook`] is called or when the hook thread
+/// or a thread marked by [`release_injected_buttons_on_panic`] panics after [`install_hook`]
+/// is called, so that the OS does not keep these buttons pressed.
+/// Panics caught by [`catch_unwind`] are excluded.
+///
+/// # Example
+///
+/// ```no_run
+/// use hookmap_core::button::Button;
+///
+/// Button::LShift.press();
+/// hookmap_core::release_injected_buttons(); // Releases LShift
+/// ```
+///
+pub fn release_injected_buttons() {
+    let pressed = std::mem::take(&mut *injected_pressed_buttons());
+    for button in pressed.into_iter().rev() {
+        button.release();
+    }
+}
+
+/// Sets buttons that call [`release_injected_buttons`] when they are physically pressed
+/// at the same time. An empty chord disables this. It is empty by default.
+///
+/// # Example
+///
+/// ```
+/// use hookmap_core::button::Button;
+///
+/// hookmap_core::set_emergency_release_chord([Button::Ctrl, Button::Alt, Button::Backspace]);
+/// ```
+///
+pub fn set_emergency_release_chord(chord: impl IntoIterator<Item = Button>) {
+    *EMERGENCY_RELEASE_CHORD
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = chord.into_iter().collect();
+}
+
+thread_local! {
+    // Whether a panic on this thread is caught by `catch_unwind`.
+    static IS_CATCHING: Cell<bool> = const { Cell::new(false) };
+
+    // Whether a panic on this thread releases the buttons pressed by this library.
+    static RELEASES_ON_PANIC: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Makes a panic on the current thread release the buttons pressed by this library
+/// (See [`release_injected_buttons`]), unless it is caught by [`catch_unwind`].
+///
+/// Panics on other threads do not release the buttons, except that every panic does
+/// if the panic strategy is `abort`, since it ends the process.
+/// The thread running the hook procedure is marked by [`install_hook`].
+///
+/// # Example
+///
+/// ```
+/// std::thread::spawn(|| {
+///     hookmap_core::release_injected_buttons_on_panic();
+///     // Handle the events.
+/// });
+/// ```
+///
+pub fn release_injected_buttons_on_panic() {
+    RELEASES_ON_PANIC.with(|releases_on_panic| releases_on_panic.set(true));
+}
+
+/// Invokes `f`, capturing the cause of an unwinding panic if one occurs.
+///
+/// This is the same as [`std::panic::catch_unwind`], except that the buttons pressed
+/// by this library are not released when `f` panics (See [`release_injected_buttons`]).
+/// Use this to recover from panics without releasing the buttons held by the program.
+///
+/// # Example
+///
+/// ```
+/// let result = hookmap_core::catch_unwind(|| panic!("oops"));
+/// assert!(result.is_err());
+/// ```
+///
+pub fn catch_unwind<R>(f: impl FnOnce() -> R + UnwindSafe) -> thread::Result<R> {
+    let was_catching = IS_CATCHING.with(|is_catching| is_catching.replace(true));
+    let result = panic::catch_unwind(f);
+    IS_CATCHING.with(|is_catching| is_catching.set(was_catching));
+    result
+}
+
+fn set_panic_hook() {
+    static SET_PANIC_HOOK: Once = Once::new();
+
+    SET_PANIC_HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let is_catching = IS_CATCHING.try_with(Cell::get).unwrap_or(false);
+            let releases_on_panic = RELEASES_ON_PANIC.try_with(Cell::get).unwrap_or(false);
+            if cfg!(panic = "abort") || (releases_on_panic && !is_catching) {
+                release_injected_buttons();
+            }
+            default_hook(info);
+        }));
+    });
+}
+
 impl Button {
     /// Simulates a button presses.
     #[inline]
@@ -131,6 +314,39 @@ impl Button {
         BUTTON_STATE.is_released(self, Ordering::SeqCst)
     }
 
+    /// Returns `true` if the button is pressed by inputs not generated by this library,
+    /// such as the user's physical input. Generic modifiers such as [`Button::Shift`]
+    /// are pressed if either side of them is pressed.
+    ///
+    /// Unlike [`Button::is_pressed`], this is not changed by [`Button::press`]
+    /// and [`Button::release`].
+    #[inline]
+    pub fn is_physically_pressed(self) -> bool {
+        match left_and_right_modifier(self) {
+            Some((left, right)) => {
+                PHYSICAL_BUTTON_STATE.is_pressed(left, Ordering::SeqCst)
+                    || PHYSICAL_BUTTON_STATE.is_pressed(right, Ordering::SeqCst)
+            }
+            None => PHYSICAL_BUTTON_STATE.is_pressed(self, Ordering::SeqCst),
+        }
+    }
+
+    /// Returns `true` if the toggle state of the button is on,
+    /// such as [`Button::NumLock`] and [`Button::ScrollLock`] with their lights on.
+    #[inline]
+    pub fn is_toggled(self) -> bool {
+        let state = unsafe { KeyboardAndMouse::GetKeyState(vkcode::from_button(self).0 as i32) };
+        state & 1 != 0
+    }
+
+    #[inline]
+    fn assume_physically(self, action: ButtonAction) {
+        match action {
+            ButtonAction::Press => PHYSICAL_BUTTON_STATE.press(self, Ordering::SeqCst),
+            ButtonAction::Release => PHYSICAL_BUTTON_STATE.release(self, Ordering::SeqCst),
+        }
+    }
+
     #[inline]
     fn assume_pressed(self) {
         BUTTON_STATE.press(self, Ordering::SeqCst);
@@ -142,10 +358,90 @@ impl Button {
     }
 }
 
+pub mod keyboard {
+    //! Functions for keyboard operations
+
+    use super::INPUT;
+    use crate::button::Button;
+    use crate::output::{self, Output};
+
+    /// Returns the button used to type `c` instead of Unicode input,
+    /// since many applications do not handle these characters sent as Unicode input.
+    fn fallback_button(c: char) -> Option<Button> {
+        match c {
+            '\n' => Some(Button::Enter),
+            '\t' => Some(Button::Tab),
+            _ => None,
+        }
+    }
+
+    fn type_text_inner(text: &str, recursive: bool) {
+        // "\r\n" is typed as a single newline.
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let c = match c {
+                '\r' if chars.peek() == Some(&'\n') => continue,
+                '\r' => '\n',
+                c => c,
+            };
+            match fallback_button(c) {
+                Some(button) if recursive => button.click_recursive(),
+                Some(button) => button.click(),
+                None => output::send_or_redirect(Output::Unicode(c), || {
+                    INPUT.unicode_input(c, recursive)
+                }),
+            }
+        }
+    }
+
+    /// Types the text, regardless of the keyboard layout.
+    ///
+    /// Characters are sent as Unicode input, except for newlines and tabs
+    /// which are sent as `Enter` and `Tab`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// hookmap_core::keyboard::type_text("Ünïcødé 🦀");
+    /// ```
+    ///
+    #[inline]
+    pub fn type_text(text: &str) {
+        type_text_inner(text, false);
+    }
+
+    /// Types the text, regardless of the keyboard layout.
+    /// Events generated by this method can be hooked.
+    #[inline]
+    pub fn type_text_recursive(text: &str) {
+        type_text_inner(text, true);
+    }
+}
+
 pub mod mouse {
     //! Functions for mouse operations
 
     use super::INPUT;
+    use crate::event::{WheelAxis, WHEEL_DELTA};
+    use crate::output::{self, Output};
+
+    fn move_absolute_inner(x: i32, y: i32, recursive: bool) {
+        output::send_or_redirect(Output::MoveTo(x, y), || {
+            INPUT.move_absolute(x, y, recursive)
+        });
+    }
+
+    fn move_relative_inner(dx: i32, dy: i32, recursive: bool) {
+        output::send_or_redirect(Output::MoveBy(dx, dy), || {
+            INPUT.move_relative(dx, dy, recursive)
+        });
+    }
+
+    fn rotate_wheel(axis: WheelAxis, hi_res_delta: i32, recursive: bool) {
+        output::send_or_redirect(Output::Wheel(axis, hi_res_delta), || {
+            INPUT.rotate_wheel(axis, hi_res_delta, recursive)
+        });
+    }
 
     /// Gets the position of the mouse cursor. `(x, y)`
     #[inline]
@@ -156,41 +452,97 @@ pub mod mouse {
     /// Moves the mouse cursor to the specified coordinates.
     #[inline]
     pub fn move_absolute(x: i32, y: i32) {
-        INPUT.move_absolute(x, y, false);
+        move_absolute_inner(x, y, false);
     }
 
     /// Moves the mouse cursor to the specified coordinates.
     /// Events generated by this method can be hooked.
     #[inline]
     pub fn move_absolute_recursive(x: i32, y: i32) {
-        INPUT.move_absolute(x, y, true);
+        move_absolute_inner(x, y, true);
     }
 
     /// Moves the mouse cursor a specified distance.
     #[inline]
     pub fn move_relative(dx: i32, dy: i32) {
-        INPUT.move_relative(dx, dy, false);
+        move_relative_inner(dx, dy, false);
     }
 
     /// Moves the mouse cursor a specified distance.
     /// Events generated by this method can be hooked.
     #[inline]
     pub fn move_relative_recursive(dx: i32, dy: i32) {
-        INPUT.move_relative(dx, dy, true);
+        move_relative_inner(dx, dy, true);
     }
 
-    /// Rotates the mouse wheel.
+    /// Rotates the mouse wheel by `speed` notches.
+    /// Positive values scroll upward.
     #[inline]
     pub fn rotate(speed: i32) {
-        INPUT.rotate_wheel(speed, false);
+        rotate_wheel(WheelAxis::Vertical, speed * WHEEL_DELTA, false);
     }
 
-    /// Rotates the mouse wheel.
+    /// Rotates the mouse wheel by `speed` notches.
+    /// Positive values scroll upward.
     /// Events generated by this method can be hooked.
     #[inline]
     pub fn rotate_recursive(speed: i32) {
-        INPUT.rotate_wheel(speed, true);
+        rotate_wheel(WheelAxis::Vertical, speed * WHEEL_DELTA, true);
+    }
+
+    /// Tilts the mouse wheel by `speed` notches.
+    /// Positive values scroll rightward.
+    #[inline]
+    pub fn rotate_horizontal(speed: i32) {
+        rotate_wheel(WheelAxis::Horizontal, speed * WHEEL_DELTA, false);
+    }
+
+    /// Tilts the mouse wheel by `speed` notches.
+    /// Positive values scroll rightward.
+    /// Events generated by this method can be hooked.
+    #[inline]
+    pub fn rotate_horizontal_recursive(speed: i32) {
+        rotate_wheel(WheelAxis::Horizontal, speed * WHEEL_DELTA, true);
     }
+
+    /// Scrolls along `axis` by `hi_res_delta` in units of 1/[`WHEEL_DELTA`] of a notch.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use hookmap_core::event::WheelAxis;
+    /// use hookmap_core::mouse;
+    ///
+    /// // A quarter of a notch downward.
+    /// mouse::rotate_hi_res(WheelAxis::Vertical, -30);
+    /// ```
+    ///
+    #[inline]
+    pub fn rotate_hi_res(axis: WheelAxis, hi_res_delta: i32) {
+        rotate_wheel(axis, hi_res_delta, false);
+    }
+
+    /// Scrolls along `axis` by `hi_res_delta` in units of 1/[`WHEEL_DELTA`] of a notch.
+    /// Events generated by this method can be hooked.
+    #[inline]
+    pub fn rotate_hi_res_recursive(axis: WheelAxis, hi_res_delta: i32) {
+        rotate_wheel(axis, hi_res_delta, true);
+    }
+}
+
+/// Returns the information about the foreground window,
+/// or `None` if no window is in the foreground.
+///
+/// # Examples
+///
+/// ```no_run
+/// if let Some(window) = hookmap_core::window::foreground_window() {
+///     println!("{}: {}", window.process_name, window.title);
+/// }
+/// ```
+///
+pub fn foreground_window() -> Option<crate::window::WindowInfo> {
+    window::foreground_window()
 }
 
 static HOOK_HANDLER: Lazy<HookHandler> = Lazy::new(HookHandler::new);
@@ -215,6 +567,10 @@ extern "system" fn mouse_hook_proc(n_code: i32, w_param: WPARAM, l_param: LPARAM
 
 /// Installs a hook and returns a receiver to receive the generated event.
 ///
+/// This also sets a process-wide panic hook that releases the buttons pressed by this library
+/// when the hook thread or a thread marked by [`release_injected_buttons_on_panic`] panics.
+/// Panics on other threads, such as worker threads and async tasks, leave them pressed.
+///
 /// # Panics
 ///
 /// Panics if other hooks are already installed.
@@ -226,20 +582,45 @@ extern "system" fn mouse_hook_proc(n_code: i32, w_param: WPARAM, l_param: LPARAM
 /// ```
 ///
 pub fn install_hook() -> EventReceiver {
+    let (tx, rx) = event::channel();
+    install(tx);
+    rx
+}
+
+/// Installs a hook and returns a stream of the generated events.
+///
+/// This sets the same panic hook as [`install_hook`].
+///
+/// # Panics
+///
+/// Panics if other hooks are already installed.
+///
+/// # Example
+///
+/// ```no_run
+/// let stream = hookmap_core::install_hook_async();
+/// ```
+///
+#[cfg(feature = "async")]
+pub fn install_hook_async() -> EventStream {
+    let (tx, stream) = event::stream_channel();
+    install(tx);
+    stream
+}
+
+fn install(event_sender: EventSender) {
     unsafe {
         // If this is not executed, the GetCursorPos function returns an invalid cursor position.
         HiDpi::SetProcessDpiAwarenessContext(HiDpi::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE);
     }
 
     INPUT.update_cursor_position();
+    set_panic_hook();
 
-    let (tx, rx) = event::channel();
-    HOOK_HANDLER.install(tx, keyboard_hook_proc, mouse_hook_proc);
-
-    rx
+    HOOK_HANDLER.install(event_sender, keyboard_hook_proc, mouse_hook_proc);
 }
 
-/// Uninstalls a hook.
+/// Uninstalls a hook and releases buttons pressed by this library (See [`release_injected_buttons`]).
 /// After this call, [`install_hook`] can be called again.
 ///
 /// # Panics
@@ -259,4 +640,5 @@ pub fn install_hook() -> EventReceiver {
 ///
 pub fn uninstall_hook() {
     HOOK_HANDLER.uninstall();
+    release_injected_buttons();
 }
@@ -1,18 +1,22 @@
 use super::input::Input;
-use super::{vkcode, INJECTED_FLAG, SHOULD_BE_IGNORED_FLAG};
+use super::{vkcode, ButtonState, INJECTED_FLAG, SHOULD_BE_IGNORED_FLAG};
 use crate::button::{Button, ButtonAction};
 use crate::event::{
-    ButtonEvent, CursorEvent, Event, EventSender, NativeEventOperation, WheelEvent,
+    ButtonEvent, CursorEvent, Event, EventSender, MonitorId, NativeEventOperation, WheelAxis,
+    WheelEvent, WHEEL_DELTA,
 };
+use crate::kill_switch;
 
 use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::{mpsc, Mutex};
 use std::thread::{self, JoinHandle};
 
 use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::Graphics::Gdi;
 use windows::Win32::System::Threading;
-use windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY;
+use windows::Win32::UI::Input::KeyboardAndMouse::{self, LASTINPUTINFO, VIRTUAL_KEY};
 use windows::Win32::UI::WindowsAndMessaging;
 
 // For many constants.
@@ -20,10 +24,78 @@ use windows::Win32::UI::WindowsAndMessaging::*;
 
 type HookProc = unsafe extern "system" fn(code: i32, WPARAM, LPARAM) -> LRESULT;
 
+// Interval to check whether the hooks have been removed by the OS.
+const HEALTH_CHECK_INTERVAL_MS: u32 = 1000;
+
+// If the hooks have not been called for this long since the last input,
+// they are considered to have been removed.
+const HOOK_REMOVED_THRESHOLD_MS: i32 = 1000;
+
+// The time of the last input that the hooks received.
+static LAST_HOOKED_INPUT_TIME: AtomicU32 = AtomicU32::new(0);
+
+fn last_input_time() -> Option<u32> {
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    let succeeded = unsafe { KeyboardAndMouse::GetLastInputInfo(&mut info).as_bool() };
+    succeeded.then_some(info.dwTime)
+}
+
+// Windows silently removes hooks that take longer than `LowLevelHooksTimeout` to return.
+// In that case, inputs continue to occur while the hooks are not called.
+fn are_hooks_removed() -> bool {
+    let last_hooked = LAST_HOOKED_INPUT_TIME.load(Ordering::SeqCst);
+    match last_input_time() {
+        Some(last_input) => last_input.wrapping_sub(last_hooked) as i32 > HOOK_REMOVED_THRESHOLD_MS,
+        None => false,
+    }
+}
+
 #[derive(Debug)]
-struct Inner {
+struct Hooks {
     keyboard_hook_handler: HHOOK,
     mouse_hook_handler: HHOOK,
+}
+
+impl Hooks {
+    unsafe fn install(keyboard_hook_proc: HookProc, mouse_hook_proc: HookProc) -> Self {
+        if let Some(time) = last_input_time() {
+            LAST_HOOKED_INPUT_TIME.store(time, Ordering::SeqCst);
+        }
+
+        let keyboard_hook_handler = WindowsAndMessaging::SetWindowsHookExW(
+            WH_KEYBOARD_LL,
+            Some(keyboard_hook_proc),
+            HINSTANCE(0),
+            0,
+        )
+        .expect("Failed to install keyboard hook.");
+
+        let mouse_hook_handler = WindowsAndMessaging::SetWindowsHookExW(
+            WH_MOUSE_LL,
+            Some(mouse_hook_proc),
+            HINSTANCE(0),
+            0,
+        )
+        .expect("Failed to install mouse hook.");
+
+        Hooks {
+            keyboard_hook_handler,
+            mouse_hook_handler,
+        }
+    }
+
+    // Fails if the hooks have already been removed by the OS, so the result is ignored.
+    unsafe fn uninstall(self) {
+        let _ = WindowsAndMessaging::UnhookWindowsHookEx(self.keyboard_hook_handler);
+        let _ = WindowsAndMessaging::UnhookWindowsHookEx(self.mouse_hook_handler);
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
     event_sender: EventSender,
     join_handle: JoinHandle<()>,
     thread_id: u32,
@@ -31,38 +103,30 @@ struct Inner {
 
 impl Inner {
     fn spawn_thread(
-        tx: Sender<(HHOOK, HHOOK, u32)>,
+        tx: Sender<u32>,
         keyboard_hook_proc: HookProc,
         mouse_hook_proc: HookProc,
     ) -> JoinHandle<()> {
         thread::spawn(move || unsafe {
-            let keyboard_hook_handler = WindowsAndMessaging::SetWindowsHookExW(
-                WH_KEYBOARD_LL,
-                Some(keyboard_hook_proc),
-                HINSTANCE(0),
-                0,
-            )
-            .expect("Failed to install keyboard hook.");
-
-            let mouse_hook_handler = WindowsAndMessaging::SetWindowsHookExW(
-                WH_MOUSE_LL,
-                Some(mouse_hook_proc),
-                HINSTANCE(0),
-                0,
-            )
-            .expect("Failed to install mouse hook.");
+            super::release_injected_buttons_on_panic();
+            let mut hooks = Hooks::install(keyboard_hook_proc, mouse_hook_proc);
 
             let thread_id = Threading::GetCurrentThreadId();
+            tx.send(thread_id).unwrap();
 
-            tx.send((keyboard_hook_handler, mouse_hook_handler, thread_id))
-                .unwrap();
+            let timer_id =
+                WindowsAndMessaging::SetTimer(HWND(0), 0, HEALTH_CHECK_INTERVAL_MS, None);
+
+            let mut msg: MSG = MaybeUninit::zeroed().assume_init();
+            while WindowsAndMessaging::GetMessageW(&mut msg, HWND(0), 0, 0).0 > 0 {
+                if msg.message == WM_TIMER && are_hooks_removed() {
+                    hooks.uninstall();
+                    hooks = Hooks::install(keyboard_hook_proc, mouse_hook_proc);
+                }
+            }
 
-            WindowsAndMessaging::GetMessageW(
-                &mut MaybeUninit::zeroed().assume_init(),
-                HWND(0),
-                0,
-                0,
-            );
+            WindowsAndMessaging::KillTimer(HWND(0), timer_id);
+            hooks.uninstall();
         })
     }
 
@@ -74,11 +138,9 @@ impl Inner {
         let (tx, rx) = mpsc::channel();
 
         let join_handle = Self::spawn_thread(tx, keyboard_hook_proc, mouse_hook_proc);
-        let (keyboard_hook_handler, mouse_hook_handler, thread_id) = rx.recv().unwrap();
+        let thread_id = rx.recv().unwrap();
 
         Inner {
-            keyboard_hook_handler,
-            mouse_hook_handler,
             event_sender,
             join_handle,
             thread_id,
@@ -87,12 +149,6 @@ impl Inner {
 
     fn uninstall(self) {
         unsafe {
-            WindowsAndMessaging::UnhookWindowsHookEx(self.keyboard_hook_handler)
-                .expect("Failed to uninstall keyboard hook.");
-
-            WindowsAndMessaging::UnhookWindowsHookEx(self.mouse_hook_handler)
-                .expect("Failed to uninstall mouse hook.");
-
             WindowsAndMessaging::PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0))
                 .unwrap();
         }
@@ -127,12 +183,15 @@ impl HookHandler {
     }
 
     pub(super) fn uninstall(&self) {
-        self.inner
+        // The lock must be released before joining the thread,
+        // since the hook procedure also acquires it.
+        let inner = self
+            .inner
             .lock()
             .unwrap()
             .take()
-            .expect("Hooks are not installed.")
-            .uninstall();
+            .expect("Hooks are not installed.");
+        inner.uninstall();
     }
 
     fn send_event(&self, event: Event) -> NativeEventOperation {
@@ -159,17 +218,74 @@ pub(super) fn create_keyboard_event(hook: &KBDLLHOOKSTRUCT) -> Option<ButtonEven
         target: vkcode::into_button(VIRTUAL_KEY(hook.vkCode as u16))?,
         injected: hook.dwExtraInfo & INJECTED_FLAG != 0,
         action,
+        is_repeat: false,
     })
 }
 
+/// Returns `true` if the press is a typematic repeat, which is delivered as a press of
+/// a button that is already pressed.
+///
+/// A physical press is checked against `physical_state`, since a button held by this library
+/// (e.g. by a remap) is not repeated when it is pressed physically.
+fn is_repeat(
+    target: Button,
+    action: ButtonAction,
+    injected: bool,
+    state: &ButtonState,
+    physical_state: &ButtonState,
+) -> bool {
+    let state = if injected { state } else { physical_state };
+    action == ButtonAction::Press && state.is_pressed(target, Ordering::SeqCst)
+}
+
 #[inline]
-fn common_hook_proc_inner(hook_handler: &HookHandler, event: Event) -> NativeEventOperation {
-    if let Event::Button(ButtonEvent { target, action, .. }) = event {
+fn common_hook_proc_inner(hook_handler: &HookHandler, mut event: Event) -> NativeEventOperation {
+    if let Event::Button(ButtonEvent {
+        target,
+        action,
+        injected,
+        ref mut is_repeat,
+    }) = event
+    {
+        *is_repeat = self::is_repeat(
+            target,
+            action,
+            injected,
+            &super::BUTTON_STATE,
+            &super::PHYSICAL_BUTTON_STATE,
+        );
         match action {
             ButtonAction::Press => target.assume_pressed(),
-            ButtonAction::Release => target.assume_released(),
+            ButtonAction::Release => {
+                target.assume_released();
+                // The button may have been pressed by this library and released physically.
+                super::track_injected_input(target, ButtonAction::Release);
+            }
         }
+        if !injected {
+            target.assume_physically(action);
+        }
+
+        if action == ButtonAction::Press
+            && !injected
+            && super::is_emergency_release_chord_pressed(target)
+        {
+            // Inputs sent in the hook procedure are delayed, so they are sent in another thread.
+            thread::spawn(super::release_injected_buttons);
+        }
+    }
+
+    if kill_switch::is_triggered() {
+        return NativeEventOperation::Dispatch;
+    }
+    if kill_switch::check(Button::is_physically_pressed) {
+        thread::spawn(|| {
+            super::release_injected_buttons();
+            kill_switch::call_handler();
+        });
+        return NativeEventOperation::Dispatch;
     }
+
     hook_handler.send_event(event)
 }
 
@@ -183,6 +299,7 @@ pub(super) fn keyboard_hook_proc_inner(
         return NativeEventOperation::Dispatch;
     }
     let hook_struct = unsafe { *(l_param.0 as *const KBDLLHOOKSTRUCT) };
+    LAST_HOOKED_INPUT_TIME.store(hook_struct.time, Ordering::SeqCst);
     let event = match create_keyboard_event(&hook_struct) {
         None => return NativeEventOperation::Dispatch,
         Some(event) => event,
@@ -199,12 +316,13 @@ pub(super) fn keyboard_hook_proc_inner(
 enum MouseEventTarget {
     Button(Button),
     Cursor,
-    Wheel,
+    Wheel(WheelAxis),
 }
 
 fn into_mouse_event_target(w_param: WPARAM, hook: &MSLLHOOKSTRUCT) -> Option<MouseEventTarget> {
     let mouse_button = match w_param.0 as u32 {
-        WM_MOUSEWHEEL => return Some(MouseEventTarget::Wheel),
+        WM_MOUSEWHEEL => return Some(MouseEventTarget::Wheel(WheelAxis::Vertical)),
+        WM_MOUSEHWHEEL => return Some(MouseEventTarget::Wheel(WheelAxis::Horizontal)),
         WM_MOUSEMOVE => return Some(MouseEventTarget::Cursor),
         WM_LBUTTONDOWN | WM_LBUTTONUP => Button::LeftButton,
         WM_RBUTTONDOWN | WM_RBUTTONUP => Button::RightButton,
@@ -236,20 +354,32 @@ fn create_mouse_event(input: &Input, w_param: WPARAM, hook: MSLLHOOKSTRUCT) -> O
     }
     let injected = hook.dwExtraInfo & INJECTED_FLAG != 0;
     let event = match into_mouse_event_target(w_param, &hook)? {
-        MouseEventTarget::Wheel => {
-            let delta = (hook.mouseData.0 as i32 >> 16) / WHEEL_DELTA as i32;
-            Event::Wheel(WheelEvent { delta, injected })
+        MouseEventTarget::Wheel(axis) => {
+            // The high-order word is the signed amount of rotation.
+            let hi_res_delta = hook.mouseData.0 as i32 >> 16;
+            Event::Wheel(WheelEvent {
+                delta: hi_res_delta / WHEEL_DELTA,
+                hi_res_delta,
+                axis,
+                injected,
+            })
         }
         MouseEventTarget::Cursor => {
-            let prev = input.cursor_position();
+            let prev = input.last_cursor_position();
             let current = hook.pt;
-            let delta = (current.x - prev.0, current.y - prev.1);
-            Event::Cursor(CursorEvent { delta, injected })
+            let monitor = unsafe { Gdi::MonitorFromPoint(current, Gdi::MONITOR_DEFAULTTONEAREST) };
+            Event::Cursor(CursorEvent {
+                delta: (current.x - prev.0, current.y - prev.1),
+                position: (current.x, current.y),
+                monitor: MonitorId(monitor.0),
+                injected,
+            })
         }
         MouseEventTarget::Button(button) => Event::Button(ButtonEvent {
             target: button,
             action: into_mouse_button_action(w_param)?,
             injected,
+            is_repeat: false,
         }),
     };
     Some(event)
@@ -267,9 +397,41 @@ pub(super) fn mouse_hook_proc_inner(
         return NativeEventOperation::Dispatch;
     }
     let hook_struct = unsafe { *(l_param.0 as *const MSLLHOOKSTRUCT) };
+    LAST_HOOKED_INPUT_TIME.store(hook_struct.time, Ordering::SeqCst);
     let event = match create_mouse_event(input, w_param, hook_struct) {
         None => return NativeEventOperation::Dispatch,
         Some(event) => event,
     };
-    common_hook_proc_inner(hook_handler, event)
+    let native_operation = common_hook_proc_inner(hook_handler, event);
+
+    // A blocked movement does not move the cursor,
+    // so the next delta is calculated from the same position.
+    if let Event::Cursor(event) = event {
+        if native_operation == NativeEventOperation::Dispatch {
+            input.set_last_cursor_position(event.position);
+        }
+    }
+    native_operation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn physical_press_of_button_held_by_library_is_not_repeat() {
+        let (state, physical_state) = (ButtonState::new(), ButtonState::new());
+        let is_repeat =
+            |action, injected| is_repeat(Button::A, action, injected, &state, &physical_state);
+
+        // A remap holds `A`.
+        state.press(Button::A, Ordering::SeqCst);
+        assert!(!is_repeat(ButtonAction::Press, false));
+        assert!(is_repeat(ButtonAction::Press, true));
+
+        // `A` is also held physically.
+        physical_state.press(Button::A, Ordering::SeqCst);
+        assert!(is_repeat(ButtonAction::Press, false));
+        assert!(!is_repeat(ButtonAction::Release, false));
+    }
 }
@@ -1,5 +1,6 @@
 use super::{vkcode, INJECTED_FLAG, SHOULD_BE_IGNORED_FLAG};
 use crate::button::{Button, ButtonAction, ButtonKind};
+use crate::event::WheelAxis;
 
 use std::{mem::MaybeUninit, sync::Mutex};
 
@@ -76,6 +77,24 @@ fn create_input_struct(button: Button, action: ButtonAction, recursive: bool) ->
     }
 }
 
+fn create_unicode_input(code_unit: u16, action: ButtonAction, recursive: bool) -> INPUT {
+    let flags = match action {
+        ButtonAction::Press => KEYEVENTF_UNICODE,
+        ButtonAction::Release => KEYEVENTF_UNICODE | KEYEVENTF_KEYUP,
+    };
+    let keybd_input = KEYBDINPUT {
+        wVk: VIRTUAL_KEY(0),
+        wScan: code_unit,
+        dwFlags: flags,
+        time: 0,
+        dwExtraInfo: create_dw_extra_info(recursive),
+    };
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 { ki: keybd_input },
+    }
+}
+
 #[inline]
 fn get_cursor_position() -> (i32, i32) {
     unsafe {
@@ -106,9 +125,28 @@ impl Input {
         }
     }
 
-    pub(super) fn rotate_wheel(&self, speed: i32, recursive: bool) {
-        let speed = speed * WHEEL_DELTA as i32;
-        let input = create_mouse_input(speed, MOUSEEVENTF_WHEEL, recursive);
+    /// Sends a character as Unicode input, regardless of the keyboard layout.
+    pub(super) fn unicode_input(&self, c: char, recursive: bool) {
+        let mut buffer = [0; 2];
+        let inputs: Vec<_> = c
+            .encode_utf16(&mut buffer)
+            .iter()
+            .flat_map(|&code_unit| {
+                [ButtonAction::Press, ButtonAction::Release]
+                    .map(|action| create_unicode_input(code_unit, action, recursive))
+            })
+            .collect();
+        unsafe {
+            KeyboardAndMouse::SendInput(&inputs, INPUT_MEM_SIZE);
+        }
+    }
+
+    pub(super) fn rotate_wheel(&self, axis: WheelAxis, hi_res_delta: i32, recursive: bool) {
+        let flags = match axis {
+            WheelAxis::Vertical => MOUSEEVENTF_WHEEL,
+            WheelAxis::Horizontal => MOUSEEVENTF_HWHEEL,
+        };
+        let input = create_mouse_input(hi_res_delta, flags, recursive);
         unsafe {
             KeyboardAndMouse::SendInput(&[input], INPUT_MEM_SIZE);
         }
@@ -118,8 +156,17 @@ impl Input {
         get_cursor_position()
     }
 
+    /// Returns the cursor position after the last movement that was not blocked.
+    pub(super) fn last_cursor_position(&self) -> (i32, i32) {
+        *self.cursor_position.lock().unwrap()
+    }
+
+    pub(super) fn set_last_cursor_position(&self, position: (i32, i32)) {
+        *self.cursor_position.lock().unwrap() = position;
+    }
+
     pub(super) fn update_cursor_position(&self) {
-        *self.cursor_position.lock().unwrap() = get_cursor_position();
+        self.set_last_cursor_position(get_cursor_position());
     }
 
     // FIXME: Since the cursor is moved with SetCursorPos, it cannot be hooked
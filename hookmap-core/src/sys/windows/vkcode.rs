@@ -176,6 +176,8 @@ pub(super) const fn into_button(vkcode: VIRTUAL_KEY) -> Option<Button> {
         VK_F23 => F23,
         VK_F24 => F24,
         VK_SNAPSHOT => PrintScreen,
+        VK_NUMLOCK => NumLock,
+        VK_SCROLL => ScrollLock,
 
         VK_VOLUME_MUTE => VolumeMute,
         VK_VOLUME_DOWN => VolumeDown,
@@ -363,6 +365,8 @@ pub(super) const fn from_button(button: Button) -> VIRTUAL_KEY {
         F23 => VK_F23,
         F24 => VK_F24,
         PrintScreen => VK_SNAPSHOT,
+        NumLock => VK_NUMLOCK,
+        ScrollLock => VK_SCROLL,
 
         VolumeMute => VK_VOLUME_MUTE,
         VolumeDown => VK_VOLUME_DOWN,
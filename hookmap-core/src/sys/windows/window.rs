@@ -0,0 +1,100 @@
+use crate::window::WindowInfo;
+
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+use once_cell::sync::Lazy;
+
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{self, HWND};
+use windows::Win32::System::Threading;
+use windows::Win32::UI::WindowsAndMessaging;
+
+// Window class names are at most 256 characters.
+const MAX_CLASS_NAME: usize = 256;
+
+// Maximum length of a path including the `\\?\` prefix.
+const MAX_PATH: usize = 32767;
+
+fn from_utf16(buffer: &[u16], len: i32) -> String {
+    String::from_utf16_lossy(&buffer[..len.max(0) as usize])
+}
+
+fn window_title(hwnd: HWND) -> String {
+    unsafe {
+        let len = WindowsAndMessaging::GetWindowTextLengthW(hwnd);
+        let mut buffer = vec![0; len.max(0) as usize + 1];
+        let len = WindowsAndMessaging::GetWindowTextW(hwnd, &mut buffer);
+        from_utf16(&buffer, len)
+    }
+}
+
+fn window_class(hwnd: HWND) -> String {
+    let mut buffer = [0; MAX_CLASS_NAME + 1];
+    let len = unsafe { WindowsAndMessaging::GetClassNameW(hwnd, &mut buffer) };
+    from_utf16(&buffer, len)
+}
+
+// The executable of the last foreground window, which is reused while the window stays in the
+// foreground, since opening the process is much slower than the other queries.
+static LAST_EXECUTABLE: Lazy<Mutex<Option<(HWND, u32, PathBuf)>>> = Lazy::new(Mutex::default);
+
+fn executable_path(hwnd: HWND) -> Option<PathBuf> {
+    let mut process_id = 0;
+    unsafe { WindowsAndMessaging::GetWindowThreadProcessId(hwnd, &mut process_id) };
+
+    let mut last = LAST_EXECUTABLE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some((_, _, path)) = last
+        .as_ref()
+        .filter(|&&(last_hwnd, last_id, _)| last_hwnd == hwnd && last_id == process_id)
+    {
+        return Some(path.clone());
+    }
+    let path = query_executable_path(process_id)?;
+    *last = Some((hwnd, process_id, path.clone()));
+    Some(path)
+}
+
+fn query_executable_path(process_id: u32) -> Option<PathBuf> {
+    unsafe {
+        let process = Threading::OpenProcess(
+            Threading::PROCESS_QUERY_LIMITED_INFORMATION,
+            false,
+            process_id,
+        )
+        .ok()?;
+
+        let mut buffer = vec![0; MAX_PATH];
+        let mut size = buffer.len() as u32;
+        let succeeded = Threading::QueryFullProcessImageNameW(
+            process,
+            Threading::PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut size,
+        )
+        .as_bool();
+        Foundation::CloseHandle(process);
+
+        succeeded.then(|| PathBuf::from(from_utf16(&buffer, size as i32)))
+    }
+}
+
+pub(super) fn foreground_window() -> Option<WindowInfo> {
+    let hwnd = unsafe { WindowsAndMessaging::GetForegroundWindow() };
+    if hwnd.0 == 0 {
+        return None;
+    }
+    let executable_path = executable_path(hwnd).unwrap_or_default();
+    let process_name = executable_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Some(WindowInfo {
+        process_name,
+        executable_path,
+        class: window_class(hwnd),
+        title: window_title(hwnd),
+    })
+}
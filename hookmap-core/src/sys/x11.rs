@@ -0,0 +1,162 @@
+use crate::window::WindowInfo;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use once_cell::sync::Lazy;
+
+use x11rb::connection::Connection;
+use x11rb::errors::ReplyError;
+use x11rb::protocol::xproto::{Atom, AtomEnum, ConnectionExt, GetPropertyReply, Window};
+use x11rb::rust_connection::RustConnection;
+
+// Atoms defined by EWMH, which are not predefined by the core protocol.
+#[derive(Debug)]
+struct Atoms {
+    net_active_window: Atom,
+    net_wm_name: Atom,
+    net_wm_pid: Atom,
+    utf8_string: Atom,
+}
+
+impl Atoms {
+    fn intern(conn: &RustConnection) -> Result<Self, ReplyError> {
+        let intern = |name: &[u8]| -> Result<Atom, ReplyError> {
+            Ok(conn.intern_atom(false, name)?.reply()?.atom)
+        };
+        Ok(Self {
+            net_active_window: intern(b"_NET_ACTIVE_WINDOW")?,
+            net_wm_name: intern(b"_NET_WM_NAME")?,
+            net_wm_pid: intern(b"_NET_WM_PID")?,
+            utf8_string: intern(b"UTF8_STRING")?,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct X11 {
+    conn: RustConnection,
+    root: Window,
+    atoms: Atoms,
+}
+
+// Properties of type `STRING` are encoded in Latin-1.
+fn from_latin1(bytes: &[u8]) -> String {
+    bytes.iter().copied().map(char::from).collect()
+}
+
+fn executable_path(process_id: u32) -> Option<PathBuf> {
+    fs::read_link(format!("/proc/{}/exe", process_id)).ok()
+}
+
+fn process_name(process_id: u32, executable_path: &Path) -> String {
+    match executable_path.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        // The executable of a process owned by another user cannot be read.
+        None => fs::read_to_string(format!("/proc/{}/comm", process_id))
+            .map(|comm| comm.trim_end().to_owned())
+            .unwrap_or_default(),
+    }
+}
+
+impl X11 {
+    fn connect() -> Option<Self> {
+        let (conn, screen) = x11rb::connect(None).ok()?;
+        let root = conn.setup().roots[screen].root;
+        let atoms = Atoms::intern(&conn).ok()?;
+        Some(Self { conn, root, atoms })
+    }
+
+    fn property(
+        &self,
+        window: Window,
+        property: impl Into<Atom>,
+        type_: impl Into<Atom>,
+    ) -> Result<GetPropertyReply, ReplyError> {
+        self.conn
+            .get_property(false, window, property, type_, 0, u32::MAX)?
+            .reply()
+    }
+
+    fn property32(
+        &self,
+        window: Window,
+        property: impl Into<Atom>,
+        type_: impl Into<Atom>,
+    ) -> Result<Option<u32>, ReplyError> {
+        let reply = self.property(window, property, type_)?;
+        Ok(reply.value32().and_then(|mut values| values.next()))
+    }
+
+    fn active_window(&self) -> Result<Option<Window>, ReplyError> {
+        let window = self.property32(self.root, self.atoms.net_active_window, AtomEnum::WINDOW)?;
+        Ok(window.filter(|&window| window != x11rb::NONE))
+    }
+
+    fn window_title(&self, window: Window) -> Result<String, ReplyError> {
+        let reply = self.property(window, self.atoms.net_wm_name, self.atoms.utf8_string)?;
+        if !reply.value.is_empty() {
+            return Ok(String::from_utf8_lossy(&reply.value).into_owned());
+        }
+        // Clients that do not support EWMH only set `WM_NAME`.
+        let reply = self.property(window, AtomEnum::WM_NAME, AtomEnum::STRING)?;
+        Ok(from_latin1(&reply.value))
+    }
+
+    // `WM_CLASS` consists of the instance name and the class name, each terminated by NUL.
+    fn window_class(&self, window: Window) -> Result<String, ReplyError> {
+        let reply = self.property(window, AtomEnum::WM_CLASS, AtomEnum::STRING)?;
+        let class = reply.value.split(|&byte| byte == 0).nth(1).unwrap_or(&[]);
+        Ok(from_latin1(class))
+    }
+
+    fn foreground_window(&self) -> Result<Option<WindowInfo>, ReplyError> {
+        let window = match self.active_window()? {
+            Some(window) => window,
+            None => return Ok(None),
+        };
+        let process_id = self.property32(window, self.atoms.net_wm_pid, AtomEnum::CARDINAL)?;
+        let executable_path = process_id.and_then(executable_path).unwrap_or_default();
+        let process_name = process_id
+            .map(|process_id| process_name(process_id, &executable_path))
+            .unwrap_or_default();
+        Ok(Some(WindowInfo {
+            process_name,
+            executable_path,
+            class: self.window_class(window)?,
+            title: self.window_title(window)?,
+        }))
+    }
+}
+
+// The connection to the X server, which is opened on the first query
+// and opened again after it is lost.
+static X11: Lazy<Mutex<Option<X11>>> = Lazy::new(Mutex::default);
+
+/// Returns the information about the foreground window,
+/// or `None` if no window is in the foreground or the X server cannot be connected.
+///
+/// # Examples
+///
+/// ```no_run
+/// if let Some(window) = hookmap_core::window::foreground_window() {
+///     println!("{}: {}", window.process_name, window.title);
+/// }
+/// ```
+///
+pub fn foreground_window() -> Option<WindowInfo> {
+    let mut x11 = X11.lock().unwrap_or_else(PoisonError::into_inner);
+    if x11.is_none() {
+        *x11 = X11::connect();
+    }
+    match x11.as_ref()?.foreground_window() {
+        Ok(window) => window,
+        // The window may have been destroyed while it was queried.
+        Err(ReplyError::X11Error(_)) => None,
+        Err(ReplyError::ConnectionError(_)) => {
+            *x11 = None;
+            None
+        }
+    }
+}
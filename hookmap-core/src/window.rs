@@ -0,0 +1,26 @@
+//! Information about windows.
+//!
+//! This is implemented for Windows and X11.
+//! Wayland is not supported; under XWayland, only windows of X11 clients can be found.
+
+use std::path::PathBuf;
+
+pub use crate::sys::foreground_window;
+
+/// Information about a top-level window.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct WindowInfo {
+    /// File name of the executable of the process that owns the window, e.g. `notepad.exe`.
+    pub process_name: String,
+
+    /// Full path of the executable of the process that owns the window.
+    /// On X11, this is empty if the process cannot be found from `_NET_WM_PID`
+    /// or its executable cannot be read.
+    pub executable_path: PathBuf,
+
+    /// Class name of the window. On X11, this is the class part of `WM_CLASS`.
+    pub class: String,
+
+    /// Title of the window.
+    pub title: String,
+}
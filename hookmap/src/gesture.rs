@@ -0,0 +1,169 @@
+use hookmap_core::button::Button;
+
+use std::time::{Duration, Instant};
+
+/// A straight movement of the mouse cursor in a gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Stroke {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Stroke {
+    fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_uppercase() {
+            'U' => Some(Stroke::Up),
+            'D' => Some(Stroke::Down),
+            'L' => Some(Stroke::Left),
+            'R' => Some(Stroke::Right),
+            _ => None,
+        }
+    }
+
+    /// Parses a pattern such as `"RL"`, in which each of `U`, `D`, `L` and `R` is a stroke.
+    pub(crate) fn parse_pattern(pattern: &str) -> Option<Vec<Self>> {
+        let strokes: Option<Vec<_>> = pattern.chars().map(Self::from_char).collect();
+        strokes.filter(|strokes| {
+            !strokes.is_empty() && strokes.windows(2).all(|pair| pair[0] != pair[1])
+        })
+    }
+}
+
+/// Recognizes strokes from the movement of the mouse cursor while `trigger` is held.
+#[derive(Debug)]
+pub(crate) struct Recognizer {
+    trigger: Button,
+    threshold: u32,
+    pressed_at: Instant,
+    movement: (i32, i32),
+    strokes: Vec<Stroke>,
+    is_passed_through: bool,
+}
+
+impl Recognizer {
+    /// The time after which the trigger held without drawing a stroke is pressed as usual.
+    pub(crate) const HOLD_TIMEOUT: Duration = Duration::from_millis(300);
+
+    pub(crate) fn new(trigger: Button, threshold: u32) -> Self {
+        Self {
+            trigger,
+            threshold,
+            pressed_at: Instant::now(),
+            movement: (0, 0),
+            strokes: Vec::new(),
+            is_passed_through: false,
+        }
+    }
+
+    pub(crate) fn trigger(&self) -> Button {
+        self.trigger
+    }
+
+    pub(crate) fn pressed_at(&self) -> Instant {
+        self.pressed_at
+    }
+
+    /// Gives up recognizing if no stroke has been drawn, so that the trigger is pressed as usual.
+    /// Returns `true` if the trigger should be pressed now.
+    pub(crate) fn pass_through(&mut self) -> bool {
+        if self.is_passed_through || !self.strokes.is_empty() {
+            return false;
+        }
+        self.is_passed_through = true;
+        true
+    }
+
+    /// Returns `true` if the trigger has been pressed by [`Recognizer::pass_through`].
+    pub(crate) fn is_passed_through(&self) -> bool {
+        self.is_passed_through
+    }
+
+    /// Adds a movement of the mouse cursor. A stroke is recognized when the cursor moves
+    /// `threshold` pixels along an axis, and is merged with the previous one in the same direction.
+    pub(crate) fn add(&mut self, delta: (i32, i32)) {
+        if self.is_passed_through {
+            return;
+        }
+        let (x, y) = (self.movement.0 + delta.0, self.movement.1 + delta.1);
+        self.movement = (x, y);
+
+        let (distance, stroke) = if x.abs() >= y.abs() {
+            (
+                x.unsigned_abs(),
+                if x > 0 { Stroke::Right } else { Stroke::Left },
+            )
+        } else {
+            (
+                y.unsigned_abs(),
+                if y > 0 { Stroke::Down } else { Stroke::Up },
+            )
+        };
+        if distance < self.threshold {
+            return;
+        }
+        self.movement = (0, 0);
+        if self.strokes.last() != Some(&stroke) {
+            self.strokes.push(stroke);
+        }
+    }
+
+    /// Returns the recognized strokes, which are empty if no gesture was performed.
+    pub(crate) fn finish(self) -> Vec<Stroke> {
+        self.strokes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_pattern() {
+        use Stroke::*;
+        assert_eq!(Stroke::parse_pattern("RL"), Some(vec![Right, Left]));
+        assert_eq!(Stroke::parse_pattern("udr"), Some(vec![Up, Down, Right]));
+        assert_eq!(Stroke::parse_pattern(""), None);
+        assert_eq!(Stroke::parse_pattern("RRL"), None);
+        assert_eq!(Stroke::parse_pattern("RX"), None);
+    }
+
+    #[test]
+    fn recognizing_strokes() {
+        let mut recognizer = Recognizer::new(Button::RightButton, 30);
+        recognizer.add((20, 3));
+        recognizer.add((15, -2));
+        recognizer.add((40, 0));
+        recognizer.add((-5, 20));
+        recognizer.add((0, 20));
+        recognizer.add((-35, 4));
+        assert_eq!(
+            recognizer.finish(),
+            vec![Stroke::Right, Stroke::Down, Stroke::Left]
+        );
+    }
+
+    #[test]
+    fn small_movements_are_not_gestures() {
+        let mut recognizer = Recognizer::new(Button::RightButton, 30);
+        recognizer.add((10, 10));
+        recognizer.add((-20, 15));
+        assert!(recognizer.finish().is_empty());
+    }
+
+    #[test]
+    fn passing_through_stops_recognizing() {
+        let mut recognizer = Recognizer::new(Button::RightButton, 30);
+        recognizer.add((10, 0));
+        assert!(recognizer.pass_through());
+        assert!(!recognizer.pass_through());
+        recognizer.add((40, 0));
+        assert!(recognizer.is_passed_through());
+        assert!(recognizer.finish().is_empty());
+
+        let mut recognizer = Recognizer::new(Button::RightButton, 30);
+        recognizer.add((40, 0));
+        assert!(!recognizer.pass_through());
+    }
+}
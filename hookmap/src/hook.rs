@@ -1,20 +1,33 @@
 use hookmap_core::button::Button;
 use hookmap_core::event::{ButtonEvent, CursorEvent, NativeEventOperation, WheelEvent};
 
+use crate::gesture::Stroke;
+
 pub(crate) trait Hook<E> {
     fn native_event_operation(&self) -> NativeEventOperation;
+
+    /// Runs on the runtime thread before [`Hook::run`], in the order of the events.
+    /// This does nothing by default.
+    fn run_in_order(&self, _event: E) {}
+
     fn run(&self, event: E);
+    fn is_exempt_from_suspension(&self, event: E) -> bool;
 }
 
 pub(crate) trait ButtonState {
     fn is_pressed(&self, button: Button) -> bool;
     fn is_released(&self, button: Button) -> bool;
+
+    /// Reflects an event that is about to be handled.
+    /// This does nothing by default, since the real state is updated by the OS.
+    fn update(&self, _event: ButtonEvent) {}
 }
 
 pub(crate) trait HookStorage {
     type ButtonHook: Hook<ButtonEvent>;
     type MouseCursorHook: Hook<CursorEvent>;
     type MouseWheelHook: Hook<WheelEvent>;
+    type GestureHook: Hook<ButtonEvent>;
 
     fn fetch_button_hook<S: ButtonState>(
         &self,
@@ -28,9 +41,26 @@ pub(crate) trait HookStorage {
         state: &S,
     ) -> Vec<Self::MouseCursorHook>;
 
+    /// Returns the rewritten movement if the movement of `event` should be rewritten.
+    fn rewrite_mouse_cursor<S: ButtonState>(
+        &self,
+        event: CursorEvent,
+        state: &S,
+    ) -> Option<(i32, i32)>;
+
     fn fetch_mouse_wheel_hook<S: ButtonState>(
         &self,
         event: WheelEvent,
         state: &S,
     ) -> Vec<Self::MouseWheelHook>;
+
+    /// Returns the threshold of strokes if gestures are drawn while `trigger` is held.
+    fn gesture_threshold<S: ButtonState>(&self, trigger: Button, state: &S) -> Option<u32>;
+
+    fn fetch_gesture_hook<S: ButtonState>(
+        &self,
+        trigger: Button,
+        strokes: &[Stroke],
+        state: &S,
+    ) -> Vec<Self::GestureHook>;
 }
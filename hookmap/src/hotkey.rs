@@ -2,20 +2,42 @@
 
 mod context;
 mod hook;
+mod motion;
+mod state;
 mod storage;
 
 pub use self::context::Context;
+pub(crate) use self::context::Modifiers;
+pub use self::hook::RemapBehavior;
+pub use self::motion::{Axis, CursorMotion};
+pub use self::state::{remove_var, set_var, var, State};
+pub use crate::runtime::suspension::{is_suspended, resume, suspend, toggle_suspension};
+pub use crate::runtime::{ReplayOutcome, RuntimeHandle};
 
-use self::hook::{Condition, HotkeyAction, HotkeyHook, MouseHook, Process, RemapHook};
+use self::hook::{
+    Condition, CursorMotionHook, GestureHook, HotkeyAction, HotkeyHook, MouseHook, Process,
+    RemapHook,
+};
 use self::storage::HotkeyStorage;
+use crate::gesture::Stroke;
 use crate::macros::button_arg::{ButtonArg, ButtonArgUnit};
+use crate::runtime::error::{CallbackId, CallbackKind};
 use crate::runtime::Runtime;
 
 use hookmap_core::button::Button;
-use hookmap_core::event::{ButtonEvent, CursorEvent, NativeEventOperation, WheelEvent};
+use hookmap_core::event::{ButtonEvent, CursorEvent, NativeEventOperation, WheelAxis, WheelEvent};
+use hookmap_core::event_log::{EventLogWriter, LogEntry};
 
+use std::io::Write;
 use std::sync::Arc;
 
+fn callback_id(kind: CallbackKind, targets: &ButtonArg) -> Arc<CallbackId> {
+    let targets = targets.iter().map(|unit| match unit {
+        ButtonArgUnit::Plain(button) | ButtonArgUnit::Not(button) => button,
+    });
+    Arc::new(CallbackId::new(kind, targets))
+}
+
 /// Registers and installs hotkeys.
 ///
 /// # Examples
@@ -85,6 +107,94 @@ impl Hotkey {
         let runtime = Runtime::new(self.storage);
         runtime.start();
     }
+
+    /// Installs hotkeys in a background thread and returns the handle to them.
+    /// Hotkeys are uninstalled when the handle is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use hookmap::prelude::*;
+    ///
+    /// let mut hotkey = Hotkey::new();
+    /// let handle = hotkey.spawn();
+    /// ```
+    ///
+    pub fn spawn(self) -> RuntimeHandle {
+        let runtime = Runtime::new(self.storage);
+        runtime.spawn()
+    }
+
+    /// Installs hotkeys and blocks the current thread like [`Hotkey::install`],
+    /// writing all the events and the decisions made for them to `writer`.
+    ///
+    /// The written log can be replayed with [`Hotkey::replay`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use hookmap::prelude::*;
+    /// use hookmap::event_log::EventLogWriter;
+    /// use std::fs::File;
+    ///
+    /// let mut hotkey = Hotkey::new();
+    /// hotkey
+    ///     .register(Context::new())
+    ///     .remap(Button::A, Button::B);
+    ///
+    /// let writer = EventLogWriter::new(File::create("events.log").unwrap()).unwrap();
+    /// hotkey.install_with_event_log(writer);
+    /// ```
+    ///
+    pub fn install_with_event_log<W>(self, writer: EventLogWriter<W>)
+    where
+        W: Write + Send + 'static,
+    {
+        let runtime = Runtime::new(self.storage);
+        runtime.start_with_event_log(writer);
+    }
+
+    /// Passes the logged events to the hotkeys without installing a hook, and returns
+    /// whether each event is blocked or dispatched and the inputs sent by the hotkeys.
+    ///
+    /// This can be used to check that the hotkeys make the same decisions as when the log
+    /// was captured. The inputs sent by the hotkeys are recorded instead of being performed
+    /// (See [`output::redirect`]), and the state of buttons seen by the hotkeys is changed
+    /// only by the replayed events and these inputs. The hotkeys are run one after another
+    /// on the current thread, so that the inputs are recorded in a deterministic order.
+    ///
+    /// [`output::redirect`]: crate::output::redirect
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    /// use hookmap::event_log::EventLogReader;
+    /// use hookmap::output::Output;
+    ///
+    /// let mut hotkey = Hotkey::new();
+    /// hotkey
+    ///     .register(Context::new())
+    ///     .remap(Button::A, Button::B);
+    ///
+    /// let log = "hookmap-event-log 1\n0 block press A\n1000 block release A\n";
+    /// let reader = EventLogReader::new(log.as_bytes()).unwrap();
+    /// let entries: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+    /// let outcome = hotkey.replay(entries.clone());
+    ///
+    /// assert!(entries.iter().map(|e| e.operation).eq(outcome.decisions));
+    /// assert_eq!(
+    ///     outcome.outputs,
+    ///     vec![
+    ///         Output::Button(Button::B, ButtonAction::Press),
+    ///         Output::Button(Button::B, ButtonAction::Release),
+    ///     ]
+    /// );
+    /// ```
+    ///
+    pub fn replay(self, entries: Vec<LogEntry>) -> ReplayOutcome {
+        Runtime::replay(self.storage, entries)
+    }
 }
 
 /// Register hotkeys.
@@ -110,6 +220,10 @@ pub struct Registrar<'a> {
 impl<'a> Registrar<'a> {
     /// Makes `target` behave like a `behavior`.
     ///
+    /// `behavior` can be a [`Button`], a chord created by [`buttons!`](crate::buttons)
+    /// or a [`Sequence`](crate::macros::sequence::Sequence) created by [`seq!`](crate::seq).
+    /// See [`RemapBehavior`] for details.
+    ///
     /// # Examples
     ///
     /// ```
@@ -118,12 +232,23 @@ impl<'a> Registrar<'a> {
     /// let mut hotkey = Hotkey::new();
     /// hotkey
     ///     .register(Context::default())
-    ///     .remap(Button::A, Button::B);
+    ///     .remap(Button::A, Button::B)
+    ///     .remap(Button::F2, buttons!(Ctrl, Shift, Alt))
+    ///     .remap(Button::F1, seq!(with(LCtrl), C));
     /// ```
     ///
-    pub fn remap(&mut self, targets: impl Into<ButtonArg>, behavior: Button) -> &mut Self {
+    pub fn remap(
+        &mut self,
+        targets: impl Into<ButtonArg>,
+        behavior: impl Into<RemapBehavior>,
+    ) -> &mut Self {
         let targets = targets.into();
-        let hook = Arc::new(RemapHook::new(self.context.to_condition(), behavior));
+        let hook = Arc::new(RemapHook::new(
+            self.context.to_condition(),
+            behavior.into(),
+            self.context.ignore_repeat,
+            self.context.key_repeat,
+        ));
         assert!(targets.is_all_plain());
 
         for target in targets.iter_plain() {
@@ -152,8 +277,9 @@ impl<'a> Registrar<'a> {
         let targets = targets.into();
         let hook = Arc::new(HotkeyHook::new(
             self.context.to_condition(),
-            HotkeyAction::Process(process.into()),
+            HotkeyAction::Process(process.into(), callback_id(CallbackKind::OnPress, &targets)),
             self.context.native_event_operation,
+            self.context.ignore_repeat,
         ));
 
         for target in targets.iter_plain() {
@@ -187,13 +313,17 @@ impl<'a> Registrar<'a> {
     ) -> &mut Self {
         let targets = targets.into();
         let condition = self.context.to_condition();
-        let process = HotkeyAction::Process(process.into());
+        let process = HotkeyAction::Process(
+            process.into(),
+            callback_id(CallbackKind::OnRelease, &targets),
+        );
 
         if self.context.has_no_modifiers() {
             let hook = Arc::new(HotkeyHook::new(
                 condition,
                 process,
                 self.context.native_event_operation,
+                self.context.ignore_repeat,
             ));
 
             for target in targets.iter_plain() {
@@ -213,11 +343,13 @@ impl<'a> Registrar<'a> {
                 Condition::Activation(Arc::clone(&is_active)),
                 process.clone(),
                 self.context.native_event_operation,
+                self.context.ignore_repeat,
             ));
             let activation_hook = Arc::new(HotkeyHook::new(
                 condition.clone(),
                 HotkeyAction::Activate(is_active),
                 NativeEventOperation::Dispatch,
+                false,
             ));
 
             match target {
@@ -247,7 +379,23 @@ impl<'a> Registrar<'a> {
         self
     }
 
-    /// Run `process` when a mouse wheel is rotated.
+    fn register_mouse_wheel(
+        &mut self,
+        axis: WheelAxis,
+        process: impl Into<Process<WheelEvent>>,
+    ) -> &mut Self {
+        let hook = Arc::new(MouseHook::new(
+            self.context.to_condition(),
+            process.into(),
+            CallbackId::new(CallbackKind::MouseWheel, []),
+            self.context.native_event_operation,
+        ));
+        self.storage.register_mouse_wheel_hotkey(axis, hook);
+        self
+    }
+
+    /// Run `process` when a mouse wheel is rotated vertically.
+    /// Horizontal scrolling is handled by [`Registrar::mouse_hwheel`].
     ///
     /// # Examples
     ///
@@ -257,20 +405,101 @@ impl<'a> Registrar<'a> {
     /// let mut hotkey = Hotkey::new();
     /// hotkey
     ///     .register(Context::default())
-    ///     .mouse_wheel(|e: WheelEvent| println!("Delta: {}", e.delta));
+    ///     .mouse_wheel(|e: WheelEvent| println!("delta: {}", e.delta));
     /// ```
     ///
     pub fn mouse_wheel(&mut self, process: impl Into<Process<WheelEvent>>) -> &mut Self {
+        self.register_mouse_wheel(WheelAxis::Vertical, process)
+    }
+
+    /// Run `process` when a mouse wheel is scrolled horizontally.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// let mut hotkey = Hotkey::new();
+    /// hotkey
+    ///     .register(Context::default())
+    ///     .mouse_hwheel(|e: WheelEvent| println!("delta: {}", e.hi_res_delta));
+    /// ```
+    ///
+    pub fn mouse_hwheel(&mut self, process: impl Into<Process<WheelEvent>>) -> &mut Self {
+        self.register_mouse_wheel(WheelAxis::Horizontal, process)
+    }
+
+    /// Run `process` when a mouse cursor is moved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// let mut hotkey = Hotkey::new();
+    /// hotkey
+    ///     .register(Context::default())
+    ///     .mouse_cursor(|e: CursorEvent| println!("movement distance: {:?}", e.delta));
+    /// ```
+    ///
+    pub fn mouse_cursor(&mut self, process: impl Into<Process<CursorEvent>>) -> &mut Self {
         let hook = Arc::new(MouseHook::new(
             self.context.to_condition(),
             process.into(),
+            CallbackId::new(CallbackKind::MouseCursor, []),
             self.context.native_event_operation,
         ));
-        self.storage.register_mouse_wheel_hotkey(hook);
+        self.storage.register_mouse_cursor_hotkey(hook);
         self
     }
 
-    /// Run `process` when a mouse cursor is moved.
+    /// Rewrites the movement of the mouse cursor with `motion`.
+    ///
+    /// The original movement is blocked and the rewritten one is sent instead.
+    /// If multiple motions can be applied, the first registered one is used.
+    /// Hotkeys registered with [`Registrar::mouse_cursor`] are not run by the rewritten movement.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    /// use hookmap::hotkey::{Axis, CursorMotion};
+    ///
+    /// let mut hotkey = Hotkey::new();
+    ///
+    /// // Precision mode while `LShift` is held.
+    /// hotkey
+    ///     .register(Context::new().modifiers(buttons!(LShift)))
+    ///     .mouse_cursor_motion(CursorMotion::new().scale(0.25));
+    ///
+    /// // Moves the cursor only vertically while `RCtrl` is held.
+    /// hotkey
+    ///     .register(Context::new().modifiers(buttons!(RCtrl)))
+    ///     .mouse_cursor_motion(CursorMotion::new().lock(Axis::Y));
+    /// ```
+    ///
+    pub fn mouse_cursor_motion(&mut self, motion: CursorMotion) -> &mut Self {
+        let hook = Arc::new(CursorMotionHook::new(self.context.to_condition(), motion));
+        self.storage.register_mouse_cursor_motion(hook);
+        self
+    }
+
+    /// Run `process` when a mouse gesture is drawn.
+    ///
+    /// A gesture is drawn by moving the mouse cursor while holding the trigger button,
+    /// which is set by [`Context::gesture_trigger`]. `pattern` is the sequence of strokes,
+    /// where `U`, `D`, `L` and `R` are strokes upward, downward, leftward and rightward.
+    /// `process` receives the release event of the trigger.
+    ///
+    /// The trigger is blocked while gestures can be drawn. If it is released without
+    /// drawing a gesture, a click of it is sent instead. If it is held for 300 milliseconds
+    /// without drawing a stroke, its press is sent late, so that holding and dragging
+    /// with the trigger work after the pause.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` is empty, contains other characters,
+    /// or contains the same stroke twice in a row.
     ///
     /// # Examples
     ///
@@ -280,16 +509,58 @@ impl<'a> Registrar<'a> {
     /// let mut hotkey = Hotkey::new();
     /// hotkey
     ///     .register(Context::default())
-    ///     .mouse_cursor(|e: CursorEvent| println!("movement distance: {:?}", e.delta));
+    ///     .gesture("L", |_| seq!(with(LAlt), LeftArrow).send())
+    ///     .gesture("RL", |_| seq!(with(LCtrl), W).send());
     /// ```
     ///
-    pub fn mouse_cursor(&mut self, process: impl Into<Process<CursorEvent>>) -> &mut Self {
-        let hook = Arc::new(MouseHook::new(
+    pub fn gesture(
+        &mut self,
+        pattern: &str,
+        process: impl Into<Process<ButtonEvent>>,
+    ) -> &mut Self {
+        let strokes = Stroke::parse_pattern(pattern)
+            .unwrap_or_else(|| panic!("Invalid gesture pattern: `{}`", pattern));
+        let trigger = self.context.gesture_trigger.unwrap_or(Button::RightButton);
+        let hook = Arc::new(GestureHook::new(
             self.context.to_condition(),
+            trigger,
+            self.context.gesture_threshold.unwrap_or(30),
+            strokes,
             process.into(),
+            CallbackId::new(CallbackKind::Gesture, [trigger]),
+        ));
+        self.storage.register_gesture(hook);
+        self
+    }
+
+    /// Toggles [`suspend`] and [`resume`] when `target` is pressed.
+    /// This hotkey works even while hotkeys are suspended.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// let mut hotkey = Hotkey::new();
+    /// hotkey
+    ///     .register(Context::new().modifiers(buttons!(LCtrl, LAlt)))
+    ///     .toggle_suspension(Button::S);
+    /// ```
+    ///
+    pub fn toggle_suspension(&mut self, targets: impl Into<ButtonArg>) -> &mut Self {
+        let hook = Arc::new(HotkeyHook::new(
+            self.context.to_condition(),
+            HotkeyAction::ToggleSuspension,
             self.context.native_event_operation,
+            self.context.ignore_repeat,
         ));
-        self.storage.register_mouse_cursor_hotkey(hook);
+        let targets = targets.into();
+        assert!(targets.is_all_plain());
+
+        for target in targets.iter_plain() {
+            self.storage
+                .register_hotkey_on_press(target, Arc::clone(&hook));
+        }
         self
     }
 
@@ -311,6 +582,7 @@ impl<'a> Registrar<'a> {
             self.context.to_condition(),
             HotkeyAction::Noop,
             NativeEventOperation::Block,
+            self.context.ignore_repeat,
         ));
         let targets = targets.into();
         assert!(targets.is_all_plain());
@@ -325,3 +597,408 @@ impl<'a> Registrar<'a> {
         self
     }
 }
+
+/// Converts an async `process` into a callback that spawns the future with `spawner`.
+///
+/// The returned callback can be passed to [`Registrar::on_press`] and the like.
+/// `spawner` receives the future each time the hotkey is triggered,
+/// and should spawn it on the runtime of the application.
+///
+/// Unlike synchronous callbacks, panics in the future are not caught by hookmap,
+/// and are handled by the runtime.
+///
+/// # Examples
+///
+/// Spawning the futures on a [tokio](https://docs.rs/tokio) runtime:
+///
+/// ```ignore
+/// use hookmap::prelude::*;
+/// use hookmap::hotkey::spawn_on;
+/// use std::time::Duration;
+///
+/// let runtime = tokio::runtime::Runtime::new().unwrap();
+/// let handle = runtime.handle().clone();
+///
+/// let mut hotkey = Hotkey::new();
+/// hotkey.register(Context::default()).on_press(
+///     buttons!(A),
+///     spawn_on(
+///         move |future| drop(handle.spawn(future)),
+///         |e: ButtonEvent| async move {
+///             tokio::time::sleep(Duration::from_millis(100)).await;
+///             println!("Pressed: {:?}", e.target);
+///         },
+///     ),
+/// );
+/// hotkey.install();
+/// ```
+///
+#[cfg(feature = "async")]
+pub fn spawn_on<E, S, F, Fut>(spawner: S, process: F) -> impl Fn(E) + Send + Sync + 'static
+where
+    S: Fn(futures_core::future::BoxFuture<'static, ()>) + Send + Sync + 'static,
+    F: Fn(E) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = ()> + Send + 'static,
+{
+    move |event| spawner(Box::pin(process(event)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buttons;
+    use hookmap_core::button::ButtonAction;
+    use hookmap_core::event_log::{self, EventLogReader};
+    use hookmap_core::output::Output;
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use NativeEventOperation::{Block, Dispatch};
+
+    fn entries(log: &[u8]) -> Vec<LogEntry> {
+        EventLogReader::new(log)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn press(button: Button) -> Output {
+        Output::Button(button, ButtonAction::Press)
+    }
+
+    fn release(button: Button) -> Output {
+        Output::Button(button, ButtonAction::Release)
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn replaying_captured_log_reproduces_decisions_and_outputs() {
+        let hotkey = || {
+            let mut hotkey = Hotkey::new();
+            hotkey.register(Context::new()).remap(Button::A, Button::B);
+            hotkey
+                .register(Context::new().modifiers(buttons!(LShift)))
+                .disable(Button::C)
+                .on_press(Button::C, |_| Button::D.click());
+            hotkey
+        };
+        let input = entries(
+            b"hookmap-event-log 3\n\
+              0 dispatch press C\n\
+              0 dispatch release C\n\
+              0 dispatch press LShift\n\
+              0 dispatch press C\n\
+              0 dispatch release C\n\
+              0 dispatch release LShift\n\
+              0 dispatch press A\n\
+              0 dispatch release A\n",
+        );
+
+        let buffer = SharedBuffer::default();
+        let (source, _) = event_log::replay(input);
+        let writer = EventLogWriter::new(buffer.clone()).unwrap();
+        let outputs = Runtime::run_virtually(hotkey().storage, event_log::capture(source, writer));
+        assert_eq!(
+            outputs,
+            vec![
+                press(Button::D),
+                release(Button::D),
+                press(Button::B),
+                release(Button::B)
+            ]
+        );
+
+        let captured = entries(&buffer.0.lock().unwrap());
+        let decisions: Vec<_> = captured.iter().map(|entry| entry.operation).collect();
+        assert_eq!(
+            decisions,
+            vec![Dispatch, Dispatch, Dispatch, Block, Block, Dispatch, Block, Block]
+        );
+
+        let outcome = hotkey().replay(captured);
+        assert_eq!(outcome.decisions, decisions);
+        assert_eq!(outcome.outputs, outputs);
+    }
+
+    #[test]
+    fn held_remap_is_released_while_suspended() {
+        let mut hotkey = Hotkey::new();
+        hotkey
+            .register(Context::new())
+            .remap(Button::A, Button::B)
+            .toggle_suspension(Button::F12);
+        let outcome = hotkey.replay(entries(
+            b"hookmap-event-log 3\n\
+              0 dispatch press A\n\
+              0 dispatch press F12\n\
+              0 dispatch release F12\n\
+              0 dispatch release A\n\
+              0 dispatch press F12\n\
+              0 dispatch release F12\n",
+        ));
+
+        assert_eq!(
+            outcome.decisions,
+            vec![Block, Dispatch, Dispatch, Block, Dispatch, Dispatch]
+        );
+        assert_eq!(outcome.outputs, vec![press(Button::B), release(Button::B)]);
+        assert!(!is_suspended());
+    }
+
+    #[test]
+    fn remap_is_not_run_while_suspended() {
+        let mut hotkey = Hotkey::new();
+        hotkey
+            .register(Context::new())
+            .remap(Button::A, Button::B)
+            .toggle_suspension(Button::F12);
+        let outcome = hotkey.replay(entries(
+            b"hookmap-event-log 3\n\
+              0 dispatch press F12\n\
+              0 dispatch release F12\n\
+              0 dispatch press A\n\
+              0 dispatch release A\n\
+              0 dispatch press F12\n\
+              0 dispatch release F12\n\
+              0 dispatch press A\n\
+              0 dispatch release A\n",
+        ));
+
+        assert_eq!(
+            outcome.decisions,
+            vec![Dispatch, Dispatch, Dispatch, Dispatch, Dispatch, Dispatch, Block, Block]
+        );
+        assert_eq!(outcome.outputs, vec![press(Button::B), release(Button::B)]);
+        assert!(!is_suspended());
+    }
+
+    const REPEATED_A: &[u8] = b"hookmap-event-log 3\n\
+        0 dispatch press A\n\
+        0 dispatch press A repeat\n\
+        0 dispatch press A repeat\n\
+        0 dispatch release A\n";
+
+    #[test]
+    fn remap_repeats_with_os() {
+        let mut hotkey = Hotkey::new();
+        hotkey.register(Context::new()).remap(Button::A, Button::B);
+        let outcome = hotkey.replay(entries(REPEATED_A));
+
+        assert_eq!(outcome.decisions, vec![Block; 4]);
+        assert_eq!(
+            outcome.outputs,
+            vec![
+                press(Button::B),
+                press(Button::B),
+                press(Button::B),
+                release(Button::B)
+            ]
+        );
+    }
+
+    #[test]
+    fn remap_ignores_repeat() {
+        let mut hotkey = Hotkey::new();
+        hotkey
+            .register(Context::new().ignore_repeat(true))
+            .remap(Button::A, Button::B);
+        let outcome = hotkey.replay(entries(REPEATED_A));
+
+        assert_eq!(outcome.decisions, vec![Block; 4]);
+        assert_eq!(outcome.outputs, vec![press(Button::B), release(Button::B)]);
+    }
+
+    #[test]
+    fn key_repeat_replaces_repeat_of_os() {
+        let mut hotkey = Hotkey::new();
+        hotkey
+            .register(Context::new().key_repeat(Duration::from_secs(60), Duration::from_secs(60)))
+            .remap(Button::A, Button::B);
+        let outcome = hotkey.replay(entries(REPEATED_A));
+
+        assert_eq!(outcome.decisions, vec![Block; 4]);
+        assert_eq!(outcome.outputs, vec![press(Button::B), release(Button::B)]);
+    }
+
+    #[test]
+    fn hotkey_ignores_repeat() {
+        let count_presses = |ignore_repeat| {
+            let count = Arc::new(AtomicUsize::new(0));
+            let counter = Arc::clone(&count);
+            let mut hotkey = Hotkey::new();
+            hotkey
+                .register(Context::new().ignore_repeat(ignore_repeat))
+                .on_press(Button::A, move |_| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                });
+            hotkey.replay(entries(REPEATED_A));
+            count.load(Ordering::SeqCst)
+        };
+
+        assert_eq!(count_presses(false), 3);
+        assert_eq!(count_presses(true), 1);
+    }
+
+    #[test]
+    fn chord_is_released_in_reverse_order() {
+        let mut hotkey = Hotkey::new();
+        hotkey
+            .register(Context::new())
+            .remap(Button::A, buttons!(LCtrl, LShift, B));
+        let outcome = hotkey.replay(entries(
+            b"hookmap-event-log 3\n\
+              0 dispatch press A\n\
+              0 dispatch release A\n",
+        ));
+
+        assert_eq!(
+            outcome.outputs,
+            vec![
+                press(Button::LCtrl),
+                press(Button::LShift),
+                press(Button::B),
+                release(Button::B),
+                release(Button::LShift),
+                release(Button::LCtrl),
+            ]
+        );
+    }
+
+    #[test]
+    fn cancelled_release_releases_chord() {
+        let mut hotkey = Hotkey::new();
+        hotkey
+            .register(Context::new())
+            .remap(Button::A, buttons!(LCtrl, LShift));
+        let outcome = hotkey.replay(entries(
+            b"hookmap-event-log 4\n\
+              0 block press A\n\
+              0 cancelled release A\n",
+        ));
+
+        assert_eq!(outcome.decisions, vec![Block, Dispatch]);
+        assert_eq!(
+            outcome.outputs,
+            vec![
+                press(Button::LCtrl),
+                press(Button::LShift),
+                release(Button::LShift),
+                release(Button::LCtrl),
+            ]
+        );
+    }
+
+    #[test]
+    fn chord_repeats_only_last_button() {
+        let mut hotkey = Hotkey::new();
+        hotkey
+            .register(Context::new())
+            .remap(Button::A, buttons!(LCtrl, B));
+        let outcome = hotkey.replay(entries(REPEATED_A));
+
+        assert_eq!(
+            outcome.outputs,
+            vec![
+                press(Button::LCtrl),
+                press(Button::B),
+                press(Button::B),
+                press(Button::B),
+                release(Button::B),
+                release(Button::LCtrl),
+            ]
+        );
+    }
+
+    #[test]
+    fn chord_is_released_even_if_it_breaks_condition() {
+        let mut hotkey = Hotkey::new();
+        hotkey
+            .register(Context::new().modifiers(buttons!(!Shift)))
+            .remap(Button::F1, buttons!(LCtrl, LShift));
+        let outcome = hotkey.replay(entries(
+            b"hookmap-event-log 3\n\
+              0 dispatch press F1\n\
+              0 dispatch press F1 repeat\n\
+              0 dispatch release F1\n",
+        ));
+
+        assert_eq!(outcome.decisions, vec![Block; 3]);
+        assert_eq!(
+            outcome.outputs,
+            vec![
+                press(Button::LCtrl),
+                press(Button::LShift),
+                press(Button::LShift),
+                release(Button::LShift),
+                release(Button::LCtrl),
+            ]
+        );
+    }
+
+    #[test]
+    fn chord_is_held_while_any_target_is_held() {
+        let mut hotkey = Hotkey::new();
+        hotkey
+            .register(Context::new())
+            .remap(buttons!(A, B), buttons!(LCtrl, C));
+        let outcome = hotkey.replay(entries(
+            b"hookmap-event-log 3\n\
+              0 dispatch press A\n\
+              0 dispatch press B\n\
+              0 dispatch release A\n\
+              0 dispatch release B\n",
+        ));
+
+        assert_eq!(outcome.decisions, vec![Block; 4]);
+        assert_eq!(
+            outcome.outputs,
+            vec![
+                press(Button::LCtrl),
+                press(Button::C),
+                press(Button::C),
+                release(Button::C),
+                release(Button::LCtrl),
+            ]
+        );
+    }
+
+    #[test]
+    fn wheel_hotkeys_are_split_by_axis() {
+        let vertical = Arc::new(AtomicUsize::new(0));
+        let horizontal = Arc::new(AtomicUsize::new(0));
+        let (v, h) = (Arc::clone(&vertical), Arc::clone(&horizontal));
+        let mut hotkey = Hotkey::new();
+        hotkey
+            .register(Context::new())
+            .mouse_wheel(move |_| {
+                v.fetch_add(1, Ordering::SeqCst);
+            })
+            .mouse_hwheel(move |_| {
+                h.fetch_add(1, Ordering::SeqCst);
+            });
+        hotkey.replay(entries(
+            b"hookmap-event-log 3\n\
+              0 dispatch wheel -1\n\
+              0 dispatch hwheel 1\n\
+              0 dispatch hwheel 0 hires 30\n",
+        ));
+
+        assert_eq!(vertical.load(Ordering::SeqCst), 1);
+        assert_eq!(horizontal.load(Ordering::SeqCst), 2);
+    }
+}
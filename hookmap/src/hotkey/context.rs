@@ -2,10 +2,13 @@ use hookmap_core::button::Button;
 use hookmap_core::event::NativeEventOperation;
 
 use super::hook::Condition;
+use super::state::{Predicate, State};
 use crate::hook::ButtonState;
 use crate::macros::button_arg::ButtonArg;
+use crate::window::WindowMatcher;
 
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Represents hotkey information.
 ///
@@ -21,7 +24,20 @@ use std::sync::Arc;
 #[derive(Debug, Default, Clone)]
 pub struct Context {
     modifiers: Option<Arc<Modifiers>>,
+    windows: Vec<Arc<WindowMatcher>>,
+    predicates: Vec<Predicate>,
     pub(crate) native_event_operation: NativeEventOperation,
+    pub(super) ignore_repeat: bool,
+    pub(super) key_repeat: Option<KeyRepeat>,
+    pub(super) gesture_trigger: Option<Button>,
+    pub(super) gesture_threshold: Option<u32>,
+}
+
+/// Timing of key repeats synthesized for remapped buttons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct KeyRepeat {
+    pub(super) delay: Duration,
+    pub(super) interval: Duration,
 }
 
 impl Context {
@@ -65,6 +81,13 @@ impl Context {
                 _ => Block,
             };
 
+        self.windows.extend(other.windows.iter().cloned());
+        self.predicates.extend(other.predicates.iter().cloned());
+        self.ignore_repeat |= other.ignore_repeat;
+        self.key_repeat = self.key_repeat.or(other.key_repeat);
+        self.gesture_trigger = self.gesture_trigger.or(other.gesture_trigger);
+        self.gesture_threshold = self.gesture_threshold.or(other.gesture_threshold);
+
         self
     }
 
@@ -83,6 +106,57 @@ impl Context {
         self
     }
 
+    /// Limits the hotkey to be registered to the time when the foreground window matches `matcher`.
+    /// If this is called multiple times or contexts are merged, all the matchers must match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    /// use hookmap::window::WindowMatcher;
+    ///
+    /// let chrome = WindowMatcher::new().class("Chrome_WidgetWin_1");
+    /// let mut hotkey = Hotkey::new();
+    /// hotkey
+    ///     .register(Context::new().when_window(chrome))
+    ///     .remap(Button::F1, Button::F5);
+    /// ```
+    ///
+    pub fn when_window(mut self, matcher: WindowMatcher) -> Self {
+        self.windows.push(Arc::new(matcher));
+        self
+    }
+
+    /// Limits the hotkey to be registered to the time when `predicate` returns `true`.
+    /// `predicate` receives the button state and the variables set by [`set_var`].
+    /// If this is called multiple times or contexts are merged, all the predicates must be `true`.
+    ///
+    /// [`set_var`]: super::set_var
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    /// use hookmap::hotkey;
+    ///
+    /// let mut hotkey = Hotkey::new();
+    /// hotkey
+    ///     .register(Context::new().when(|state| state.is_toggled(Button::NumLock)))
+    ///     .remap(Button::Numpad0, Button::LeftButton);
+    ///
+    /// hotkey
+    ///     .register(Context::new().when(|state| state.flag("recording")))
+    ///     .on_press(Button::Esc, |_| hotkey::set_var("recording", false));
+    /// ```
+    ///
+    pub fn when<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&State) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.push(Predicate::new(predicate));
+        self
+    }
+
     /// Indicates whether to block the native event when the hotkey is active.
     ///
     /// # Examples
@@ -98,14 +172,111 @@ impl Context {
         self
     }
 
+    /// Indicates whether to ignore auto-repeated presses of a held button.
+    /// If `true`, hotkeys registered with this context are not run by
+    /// the events whose [`ButtonEvent::is_repeat`] is `true`.
+    ///
+    /// [`ButtonEvent::is_repeat`]: hookmap_core::event::ButtonEvent::is_repeat
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// let mut hotkey = Hotkey::new();
+    /// hotkey
+    ///     .register(Context::new().ignore_repeat(true))
+    ///     .on_press(Button::A, |_| println!("Pressed once"));
+    /// ```
+    ///
+    pub fn ignore_repeat(mut self, ignore: bool) -> Self {
+        self.ignore_repeat = ignore;
+        self
+    }
+
+    /// Makes buttons remapped with this context repeat their output
+    /// after being held for `delay`, every `interval`, instead of following
+    /// the auto-repeat of the OS.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let context = Context::new().key_repeat(Duration::from_millis(300), Duration::from_millis(30));
+    /// let mut hotkey = Hotkey::new();
+    /// hotkey
+    ///     .register(context)
+    ///     .remap(Button::F1, Button::Backspace);
+    /// ```
+    ///
+    pub fn key_repeat(mut self, delay: Duration, interval: Duration) -> Self {
+        self.key_repeat = Some(KeyRepeat { delay, interval });
+        self
+    }
+
+    /// Sets the button held while drawing gestures registered with this context.
+    /// The default is [`Button::RightButton`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// let mut hotkey = Hotkey::new();
+    /// hotkey
+    ///     .register(Context::new().gesture_trigger(Button::MiddleButton))
+    ///     .gesture("D", |_| println!("Down"));
+    /// ```
+    ///
+    pub fn gesture_trigger(mut self, trigger: Button) -> Self {
+        self.gesture_trigger = Some(trigger);
+        self
+    }
+
+    /// Sets the distance in pixels the mouse cursor must move to draw a stroke of a gesture.
+    /// The default is `30`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// let mut hotkey = Hotkey::new();
+    /// hotkey
+    ///     .register(Context::new().gesture_threshold(50))
+    ///     .gesture("UD", |_| println!("Up, Down"));
+    /// ```
+    ///
+    pub fn gesture_threshold(mut self, pixels: u32) -> Self {
+        self.gesture_threshold = Some(pixels);
+        self
+    }
+
     pub(super) fn has_no_modifiers(&self) -> bool {
         self.modifiers.is_none()
     }
 
     pub(super) fn to_condition(&self) -> Condition {
-        self.modifiers
-            .clone()
-            .map_or(Condition::Any, Condition::Modifier)
+        let mut conditions: Vec<_> = self
+            .modifiers
+            .iter()
+            .cloned()
+            .map(Condition::Modifier)
+            .chain(self.windows.iter().cloned().map(Condition::Window))
+            .chain(self.predicates.iter().cloned().map(Condition::Predicate))
+            .collect();
+        match conditions.len() {
+            0 => Condition::Any,
+            1 => conditions.pop().unwrap(),
+            _ => Condition::All(conditions),
+        }
+    }
+
+    /// Returns `true` if the modifiers, windows and predicates of this context are satisfied.
+    pub(crate) fn is_satisfied(&self, state: &impl ButtonState) -> bool {
+        self.to_condition().is_satisfied(state)
     }
 
     pub(super) fn iter_pressed(&self) -> impl Iterator<Item = &Button> {
@@ -140,7 +311,7 @@ impl Modifiers {
                 .collect(),
         }
     }
-    pub(super) fn is_matched(&self, state: &impl ButtonState) -> bool {
+    pub(crate) fn is_matched(&self, state: &impl ButtonState) -> bool {
         self.iter_pressed().all(|&b| state.is_pressed(b))
             && self.iter_released().all(|&b| state.is_released(b))
     }
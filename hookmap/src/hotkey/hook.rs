@@ -1,14 +1,25 @@
 use hookmap_core::button::{Button, ButtonAction};
 use hookmap_core::event::{ButtonEvent, NativeEventOperation};
 
-use super::context::Modifiers;
+use super::context::{KeyRepeat, Modifiers};
+use super::motion::CursorMotion;
+use super::state::Predicate;
+use crate::gesture::Stroke;
 use crate::hook::{ButtonState, Hook};
+use crate::macros::button_arg::ButtonArg;
+use crate::macros::sequence::Sequence;
+use crate::recorder::Recording;
+use crate::runtime::error::{self, CallbackId};
+use crate::runtime::suspension;
+use crate::window::WindowMatcher;
 
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex,
 };
+use std::thread;
 
 #[derive(Clone)]
 pub struct Process<E>(Arc<dyn Fn(E) + Send + Sync>);
@@ -31,35 +42,51 @@ impl<E, F: Fn(E) + Send + Sync + 'static> From<Arc<F>> for Process<E> {
     }
 }
 
+impl<E> From<Recording> for Process<E> {
+    fn from(recording: Recording) -> Self {
+        Process(Arc::new(move |_| recording.replay()))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(super) enum Condition {
     Any,
     Activation(Arc<AtomicBool>),
     Modifier(Arc<Modifiers>),
+    Window(Arc<WindowMatcher>),
+    Predicate(Predicate),
+    All(Vec<Condition>),
 }
 
 impl Condition {
-    fn is_satisfied(&self, state: &impl ButtonState) -> bool {
+    pub(super) fn is_satisfied(&self, state: &impl ButtonState) -> bool {
         match self {
             Condition::Any => true,
             Condition::Activation(is_active) => is_active.swap(false, Ordering::SeqCst),
             Condition::Modifier(modifiers) => modifiers.is_matched(state),
+            Condition::Window(matcher) => matcher.is_foreground(),
+            Condition::Predicate(predicate) => predicate.is_satisfied(state),
+            Condition::All(conditions) => conditions.iter().all(|c| c.is_satisfied(state)),
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub(super) enum HotkeyAction<E> {
-    Process(Process<E>),
+    Process(Process<E>, Arc<CallbackId>),
     Activate(Arc<AtomicBool>),
+    ToggleSuspension,
     Noop,
 }
 
 impl<E> HotkeyAction<E> {
     pub(super) fn run(&self, event: E) {
         match self {
-            HotkeyAction::Process(callback) => callback.0(event),
+            HotkeyAction::Process(callback, id) => {
+                error::catch(id, || callback.0(event));
+            }
             HotkeyAction::Activate(is_active) => is_active.store(true, Ordering::SeqCst),
+            HotkeyAction::ToggleSuspension => suspension::toggle_suspension(),
             HotkeyAction::Noop => {}
         }
     }
@@ -70,6 +97,7 @@ pub(super) struct HotkeyHook {
     condition: Condition,
     action: HotkeyAction<ButtonEvent>,
     native_event_operation: NativeEventOperation,
+    ignore_repeat: bool,
 }
 
 impl HotkeyHook {
@@ -77,11 +105,13 @@ impl HotkeyHook {
         condition: Condition,
         action: HotkeyAction<ButtonEvent>,
         native_event_operation: NativeEventOperation,
+        ignore_repeat: bool,
     ) -> Self {
         HotkeyHook {
             condition,
             action,
             native_event_operation,
+            ignore_repeat,
         }
     }
 
@@ -90,21 +120,184 @@ impl HotkeyHook {
     }
 }
 
+/// The output of a remapped button. This is passed to [`Registrar::remap`].
+///
+/// [`Registrar::remap`]: super::Registrar::remap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemapBehavior {
+    /// Presses all the buttons in order while any of the targets is pressed,
+    /// and releases them in reverse order when all of them are released.
+    Chord(Vec<Button>),
+
+    /// Sends the sequence each time the target is pressed, including key repeats.
+    Sequence(Sequence),
+}
+
+impl From<Button> for RemapBehavior {
+    fn from(button: Button) -> Self {
+        RemapBehavior::Chord(vec![button])
+    }
+}
+
+impl From<ButtonArg> for RemapBehavior {
+    fn from(buttons: ButtonArg) -> Self {
+        assert!(buttons.is_all_plain());
+        RemapBehavior::Chord(buttons.iter_plain().collect())
+    }
+}
+
+impl From<Sequence> for RemapBehavior {
+    fn from(seq: Sequence) -> Self {
+        RemapBehavior::Sequence(seq)
+    }
+}
+
 #[derive(Debug)]
 pub(super) struct RemapHook {
     condition: Condition,
-    button: Button,
+    behavior: RemapBehavior,
+    ignore_repeat: bool,
+    key_repeat: Option<KeyRepeat>,
+
+    // Incremented each time a target is pressed,
+    // to stop the synthesized repeat of the previous press.
+    generation: AtomicU64,
+
+    // The targets pressed and not released yet, with the generation of their press.
+    // The buttons of the chord are held while any of them is held.
+    held: Mutex<HashMap<Button, u64>>,
 }
 
 impl RemapHook {
-    pub(super) fn new(condition: Condition, button: Button) -> Self {
+    pub(super) fn new(
+        condition: Condition,
+        behavior: RemapBehavior,
+        ignore_repeat: bool,
+        key_repeat: Option<KeyRepeat>,
+    ) -> Self {
         assert!(!matches!(condition, Condition::Activation(_)));
-        RemapHook { condition, button }
+        RemapHook {
+            condition,
+            behavior,
+            ignore_repeat,
+            key_repeat,
+            generation: AtomicU64::new(0),
+            held: Mutex::default(),
+        }
     }
 
     pub(super) fn is_executable(&self, state: &impl ButtonState) -> bool {
         self.condition.is_satisfied(state)
     }
+
+    /// Returns `true` if `event` sends the output, including the key repeats of the OS.
+    fn is_triggered_by(&self, event: ButtonEvent) -> bool {
+        match event.action {
+            // Repeats of the OS are replaced with the synthesized ones.
+            ButtonAction::Press if event.is_repeat => {
+                self.key_repeat.is_none() && !self.ignore_repeat
+            }
+            ButtonAction::Press => true,
+            ButtonAction::Release => false,
+        }
+    }
+
+    /// Returns `true` if `target` is pressed and not released yet.
+    pub(super) fn is_held(&self, target: Button) -> bool {
+        self.held.lock().unwrap().contains_key(&target)
+    }
+
+    fn press(&self) {
+        if let RemapBehavior::Chord(buttons) = &self.behavior {
+            buttons.iter().copied().for_each(Button::press);
+        }
+    }
+
+    fn repeat(&self) {
+        match &self.behavior {
+            // Like a key repeat of a physical chord, only the last button is repeated.
+            RemapBehavior::Chord(buttons) => {
+                buttons.last().copied().into_iter().for_each(Button::press);
+            }
+            RemapBehavior::Sequence(seq) => seq.send(),
+        }
+    }
+
+    fn release(&self) {
+        if let RemapBehavior::Chord(buttons) = &self.behavior {
+            buttons.iter().rev().copied().for_each(Button::release);
+        }
+    }
+
+    fn is_repeating(&self, target: Button, generation: u64) -> bool {
+        self.held.lock().unwrap().get(&target) == Some(&generation)
+            && target.is_physically_pressed()
+            && !suspension::is_suspended()
+    }
+
+    fn spawn_key_repeat(self: &Arc<Self>, target: Button, generation: u64) {
+        let KeyRepeat { delay, interval } = match self.key_repeat {
+            Some(key_repeat) => key_repeat,
+            None => return,
+        };
+        // Not to keep the hook alive after the hotkeys are uninstalled.
+        let hook = Arc::downgrade(self);
+        thread::spawn(move || {
+            thread::sleep(delay);
+            loop {
+                match hook.upgrade() {
+                    Some(hook) if hook.is_repeating(target, generation) => hook.repeat(),
+                    _ => break,
+                }
+                thread::sleep(interval);
+            }
+        });
+    }
+
+    fn run_in_order(self: &Arc<Self>, event: ButtonEvent) {
+        match event.action {
+            ButtonAction::Press if event.is_repeat => {
+                if self.is_triggered_by(event) {
+                    if let RemapBehavior::Chord(_) = self.behavior {
+                        self.repeat();
+                    }
+                }
+            }
+            ButtonAction::Press => {
+                let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+                let mut held = self.held.lock().unwrap();
+                let is_first = held.is_empty();
+                held.insert(event.target, generation);
+                drop(held);
+
+                // Another target pressed while the chord is held types the last button again.
+                if is_first {
+                    self.press();
+                } else if let RemapBehavior::Chord(_) = self.behavior {
+                    self.repeat();
+                }
+                self.spawn_key_repeat(event.target, generation);
+            }
+            ButtonAction::Release => {
+                let mut held = self.held.lock().unwrap();
+                let was_held = held.remove(&event.target).is_some();
+                let is_last = held.is_empty();
+                drop(held);
+
+                if was_held && is_last {
+                    self.release();
+                }
+            }
+        }
+    }
+
+    fn run(&self, event: ButtonEvent) {
+        if let RemapBehavior::Sequence(seq) = &self.behavior {
+            if self.is_triggered_by(event) {
+                seq.send();
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -121,13 +314,27 @@ impl Hook<ButtonEvent> for ButtonHook {
         }
     }
 
+    fn run_in_order(&self, event: ButtonEvent) {
+        if let ButtonHook::Remap(hook) = self {
+            hook.run_in_order(event);
+        }
+    }
+
     fn run(&self, event: ButtonEvent) {
         match self {
+            ButtonHook::Hotkey(hook) if hook.ignore_repeat && event.is_repeat => {}
             ButtonHook::Hotkey(hook) => hook.action.run(event),
-            ButtonHook::Remap(hook) => match event.action {
-                ButtonAction::Press => hook.button.press(),
-                ButtonAction::Release => hook.button.release(),
-            },
+            ButtonHook::Remap(hook) => hook.run(event),
+        }
+    }
+
+    fn is_exempt_from_suspension(&self, event: ButtonEvent) -> bool {
+        match self {
+            ButtonHook::Hotkey(hook) => matches!(hook.action, HotkeyAction::ToggleSuspension),
+            // The buttons pressed before the suspension must not be kept pressed.
+            ButtonHook::Remap(hook) => {
+                event.action == ButtonAction::Release && hook.is_held(event.target)
+            }
         }
     }
 }
@@ -146,6 +353,7 @@ impl From<Arc<RemapHook>> for ButtonHook {
 pub(super) struct MouseHook<E> {
     condition: Condition,
     process: Process<E>,
+    id: CallbackId,
     native_event_operation: NativeEventOperation,
 }
 
@@ -153,12 +361,14 @@ impl<E> MouseHook<E> {
     pub(super) fn new(
         condition: Condition,
         process: Process<E>,
+        id: CallbackId,
         native_event_operation: NativeEventOperation,
     ) -> Self {
         assert!(!matches!(condition, Condition::Activation(_)));
         MouseHook {
             condition,
             process,
+            id,
             native_event_operation,
         }
     }
@@ -176,7 +386,93 @@ impl<E> Hook<E> for MouseHook<E> {
     }
 
     fn run(&self, event: E) {
-        self.process.0(event);
+        error::catch(&self.id, || self.process.0(event));
+    }
+
+    fn is_exempt_from_suspension(&self, _: E) -> bool {
+        false
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct CursorMotionHook {
+    condition: Condition,
+    motion: CursorMotion,
+}
+
+impl CursorMotionHook {
+    pub(super) fn new(condition: Condition, motion: CursorMotion) -> Self {
+        assert!(!matches!(condition, Condition::Activation(_)));
+        CursorMotionHook { condition, motion }
+    }
+
+    pub(super) fn is_executable(&self, state: &impl ButtonState) -> bool {
+        self.condition.is_satisfied(state)
+    }
+
+    pub(super) fn apply(&self, delta: (i32, i32)) -> (i32, i32) {
+        self.motion.apply(delta)
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct GestureHook {
+    condition: Condition,
+    trigger: Button,
+    threshold: u32,
+    strokes: Vec<Stroke>,
+    process: Process<ButtonEvent>,
+    id: CallbackId,
+}
+
+impl GestureHook {
+    pub(super) fn new(
+        condition: Condition,
+        trigger: Button,
+        threshold: u32,
+        strokes: Vec<Stroke>,
+        process: Process<ButtonEvent>,
+        id: CallbackId,
+    ) -> Self {
+        assert!(!matches!(condition, Condition::Activation(_)));
+        GestureHook {
+            condition,
+            trigger,
+            threshold,
+            strokes,
+            process,
+            id,
+        }
+    }
+
+    pub(super) fn trigger(&self) -> Button {
+        self.trigger
+    }
+
+    pub(super) fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    pub(super) fn is_executable(&self, state: &impl ButtonState) -> bool {
+        self.condition.is_satisfied(state)
+    }
+
+    pub(super) fn matches(&self, trigger: Button, strokes: &[Stroke]) -> bool {
+        self.trigger == trigger && self.strokes == strokes
+    }
+}
+
+impl Hook<ButtonEvent> for GestureHook {
+    fn native_event_operation(&self) -> NativeEventOperation {
+        NativeEventOperation::Block
+    }
+
+    fn run(&self, event: ButtonEvent) {
+        error::catch(&self.id, || self.process.0(event));
+    }
+
+    fn is_exempt_from_suspension(&self, _: ButtonEvent) -> bool {
+        false
     }
 }
 
@@ -185,7 +481,15 @@ impl<E, T: Hook<E>> Hook<E> for Arc<T> {
         (**self).native_event_operation()
     }
 
+    fn run_in_order(&self, event: E) {
+        (**self).run_in_order(event);
+    }
+
     fn run(&self, event: E) {
         (**self).run(event);
     }
+
+    fn is_exempt_from_suspension(&self, event: E) -> bool {
+        (**self).is_exempt_from_suspension(event)
+    }
 }
@@ -0,0 +1,127 @@
+use std::sync::{Mutex, PoisonError};
+
+/// An axis of mouse cursor movement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    /// Horizontal
+    X,
+
+    /// Vertical
+    Y,
+}
+
+/// Rewrites mouse cursor movement. This is passed to [`Registrar::mouse_cursor_motion`].
+///
+/// [`Registrar::mouse_cursor_motion`]: super::Registrar::mouse_cursor_motion
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::hotkey::{Axis, CursorMotion};
+///
+/// // Moves at half speed only horizontally, with the direction reversed.
+/// let motion = CursorMotion::new().scale(0.5).invert(Axis::X).lock(Axis::X);
+/// ```
+///
+#[derive(Debug)]
+pub struct CursorMotion {
+    scale: (f64, f64),
+    lock: Option<Axis>,
+
+    // Fractions of movement that have not been sent yet,
+    // so that slow movements are not lost when scaled down.
+    remainder: Mutex<(f64, f64)>,
+}
+
+impl Default for CursorMotion {
+    fn default() -> Self {
+        Self {
+            scale: (1.0, 1.0),
+            lock: None,
+            remainder: Mutex::default(),
+        }
+    }
+}
+
+impl CursorMotion {
+    /// Creates a new instance of [`CursorMotion`] that does not change the movement.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Multiplies the movement along both axes by `factor`.
+    pub fn scale(self, factor: f64) -> Self {
+        self.scale_axes(factor, factor)
+    }
+
+    /// Multiplies the horizontal movement by `x` and the vertical movement by `y`.
+    pub fn scale_axes(mut self, x: f64, y: f64) -> Self {
+        self.scale.0 *= x;
+        self.scale.1 *= y;
+        self
+    }
+
+    /// Reverses the direction of movement along `axis`.
+    pub fn invert(self, axis: Axis) -> Self {
+        match axis {
+            Axis::X => self.scale_axes(-1.0, 1.0),
+            Axis::Y => self.scale_axes(1.0, -1.0),
+        }
+    }
+
+    /// Discards the movement except along `axis`.
+    pub fn lock(mut self, axis: Axis) -> Self {
+        self.lock = Some(axis);
+        self
+    }
+
+    /// Returns the rewritten movement of `delta`.
+    pub(super) fn apply(&self, delta: (i32, i32)) -> (i32, i32) {
+        let (mut x, mut y) = (
+            f64::from(delta.0) * self.scale.0,
+            f64::from(delta.1) * self.scale.1,
+        );
+        match self.lock {
+            Some(Axis::X) => y = 0.0,
+            Some(Axis::Y) => x = 0.0,
+            None => {}
+        }
+
+        let mut remainder = self
+            .remainder
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        x += remainder.0;
+        y += remainder.1;
+        let (dx, dy) = (x.trunc(), y.trunc());
+        *remainder = (x - dx, y - dy);
+        (dx as i32, dy as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn motion_is_scaled_inverted_and_locked() {
+        assert_eq!(CursorMotion::new().apply((3, -4)), (3, -4));
+        assert_eq!(CursorMotion::new().scale(2.0).apply((3, -4)), (6, -8));
+        assert_eq!(CursorMotion::new().invert(Axis::Y).apply((3, -4)), (3, 4));
+        assert_eq!(CursorMotion::new().lock(Axis::X).apply((3, -4)), (3, 0));
+        assert_eq!(
+            CursorMotion::new()
+                .scale_axes(2.0, 3.0)
+                .lock(Axis::Y)
+                .apply((3, -4)),
+            (0, -12)
+        );
+    }
+
+    #[test]
+    fn fractions_of_scaled_motion_are_accumulated() {
+        let motion = CursorMotion::new().scale(0.25);
+        let moved: Vec<_> = (0..4).map(|_| motion.apply((1, -2))).collect();
+        assert_eq!(moved, vec![(0, 0), (0, -1), (0, 0), (1, -1)]);
+    }
+}
@@ -0,0 +1,179 @@
+use hookmap_core::button::Button;
+
+use crate::hook::ButtonState;
+use crate::runtime::error::{self, CallbackId, CallbackKind};
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use once_cell::sync::Lazy;
+
+type Variables = HashMap<String, Arc<dyn Any + Send + Sync>>;
+
+// Copied on write, so that predicates can read a snapshot without copying the variables.
+static VARIABLES: Lazy<RwLock<Arc<Variables>>> = Lazy::new(RwLock::default);
+
+fn variables() -> Arc<Variables> {
+    Arc::clone(&VARIABLES.read().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// Sets the user-defined variable `name` to `value`.
+/// This can be read in the predicates passed to [`Context::when`].
+///
+/// [`Context::when`]: super::Context::when
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::hotkey;
+///
+/// hotkey::set_var("recording", true);
+/// assert_eq!(hotkey::var::<bool>("recording"), Some(true));
+/// ```
+///
+pub fn set_var<T: Any + Send + Sync>(name: impl Into<String>, value: T) {
+    let mut variables = VARIABLES.write().unwrap_or_else(PoisonError::into_inner);
+    Arc::make_mut(&mut variables).insert(name.into(), Arc::new(value));
+}
+
+/// Removes the user-defined variable `name`.
+pub fn remove_var(name: &str) {
+    let mut variables = VARIABLES.write().unwrap_or_else(PoisonError::into_inner);
+    if variables.contains_key(name) {
+        Arc::make_mut(&mut variables).remove(name);
+    }
+}
+
+/// Returns the value of the user-defined variable `name`,
+/// or `None` if it is not set or is not of type `T`.
+pub fn var<T: Any + Clone>(name: &str) -> Option<T> {
+    variables().get(name)?.downcast_ref().cloned()
+}
+
+/// A snapshot of the button state and the user-defined variables,
+/// passed to the predicates of [`Context::when`].
+///
+/// [`Context::when`]: super::Context::when
+pub struct State<'a> {
+    buttons: &'a dyn ButtonState,
+    variables: Arc<Variables>,
+}
+
+impl Debug for State<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("State")
+            .field("variables", &self.variables.keys())
+            .finish()
+    }
+}
+
+impl State<'_> {
+    /// Returns `true` if `button` is pressed.
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.buttons.is_pressed(button)
+    }
+
+    /// Returns `true` if `button` is released.
+    pub fn is_released(&self, button: Button) -> bool {
+        self.buttons.is_released(button)
+    }
+
+    /// Returns `true` if the toggle state of `button` is on. See [`Button::is_toggled`].
+    pub fn is_toggled(&self, button: Button) -> bool {
+        button.is_toggled()
+    }
+
+    /// Returns the value of the user-defined variable `name`,
+    /// or `None` if it is not set or is not of type `T`.
+    pub fn var<T: Any + Clone>(&self, name: &str) -> Option<T> {
+        self.variables.get(name)?.downcast_ref().cloned()
+    }
+
+    /// Returns `true` if the user-defined variable `name` is set to `true`.
+    pub fn flag(&self, name: &str) -> bool {
+        self.var(name).unwrap_or(false)
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct Predicate(Arc<dyn Fn(&State) -> bool + Send + Sync>);
+
+impl Debug for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Predicate")
+    }
+}
+
+impl Predicate {
+    pub(super) fn new<F>(predicate: F) -> Self
+    where
+        F: Fn(&State) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(predicate))
+    }
+
+    pub(super) fn is_satisfied(&self, buttons: &dyn ButtonState) -> bool {
+        let state = State {
+            buttons,
+            // A snapshot is taken so that the predicate can set variables without a deadlock.
+            variables: variables(),
+        };
+        let id = CallbackId::new(CallbackKind::Condition, []);
+        error::catch(&id, || self.0(&state)).unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Pressed(Vec<Button>);
+
+    impl ButtonState for Pressed {
+        fn is_pressed(&self, button: Button) -> bool {
+            self.0.contains(&button)
+        }
+
+        fn is_released(&self, button: Button) -> bool {
+            !self.is_pressed(button)
+        }
+    }
+
+    #[test]
+    fn predicate_reads_buttons_and_variables() {
+        let predicate =
+            Predicate::new(|state| state.flag("state_test_enabled") && state.is_pressed(Button::A));
+        let pressed = Pressed(vec![Button::A]);
+        assert!(!predicate.is_satisfied(&pressed));
+
+        set_var("state_test_enabled", true);
+        assert!(predicate.is_satisfied(&pressed));
+        assert!(!predicate.is_satisfied(&Pressed(vec![])));
+
+        set_var("state_test_enabled", 1);
+        assert!(!predicate.is_satisfied(&pressed));
+        remove_var("state_test_enabled");
+        assert_eq!(var::<i32>("state_test_enabled"), None);
+    }
+
+    #[test]
+    fn snapshot_is_not_changed_by_writes() {
+        set_var("state_test_snapshot", 1);
+        let snapshot = variables();
+        set_var("state_test_snapshot", 2);
+        assert_eq!(
+            snapshot["state_test_snapshot"].downcast_ref::<i32>(),
+            Some(&1)
+        );
+        assert_eq!(var::<i32>("state_test_snapshot"), Some(2));
+        remove_var("state_test_snapshot");
+    }
+
+    #[test]
+    fn panicking_predicate_is_not_satisfied() {
+        let predicate = Predicate::new(|_| panic!("predicate"));
+        assert!(!predicate.is_satisfied(&Pressed(vec![])));
+    }
+}
@@ -1,7 +1,8 @@
 use hookmap_core::button::{Button, ButtonAction};
-use hookmap_core::event::{ButtonEvent, CursorEvent, WheelEvent};
+use hookmap_core::event::{ButtonEvent, CursorEvent, WheelAxis, WheelEvent};
 
-use super::hook::{ButtonHook, HotkeyHook, MouseHook, RemapHook};
+use super::hook::{ButtonHook, CursorMotionHook, GestureHook, HotkeyHook, MouseHook, RemapHook};
+use crate::gesture::Stroke;
 use crate::hook::{ButtonState, HookStorage};
 use std::{collections::HashMap, sync::Arc};
 
@@ -11,7 +12,10 @@ pub(super) struct HotkeyStorage {
     hotkey_on_press: HashMap<Button, Vec<Arc<HotkeyHook>>>,
     hotkey_on_release: HashMap<Button, Vec<Arc<HotkeyHook>>>,
     mouse_cursor: Vec<Arc<MouseHook<CursorEvent>>>,
+    mouse_cursor_motion: Vec<Arc<CursorMotionHook>>,
     mouse_wheel: Vec<Arc<MouseHook<WheelEvent>>>,
+    mouse_hwheel: Vec<Arc<MouseHook<WheelEvent>>>,
+    gesture: Vec<Arc<GestureHook>>,
 }
 
 impl HotkeyStorage {
@@ -42,8 +46,23 @@ impl HotkeyStorage {
         self.mouse_cursor.push(hook);
     }
 
-    pub(super) fn register_mouse_wheel_hotkey(&mut self, hook: Arc<MouseHook<WheelEvent>>) {
-        self.mouse_wheel.push(hook);
+    pub(super) fn register_mouse_cursor_motion(&mut self, hook: Arc<CursorMotionHook>) {
+        self.mouse_cursor_motion.push(hook);
+    }
+
+    pub(super) fn register_mouse_wheel_hotkey(
+        &mut self,
+        axis: WheelAxis,
+        hook: Arc<MouseHook<WheelEvent>>,
+    ) {
+        match axis {
+            WheelAxis::Vertical => self.mouse_wheel.push(hook),
+            WheelAxis::Horizontal => self.mouse_hwheel.push(hook),
+        }
+    }
+
+    pub(super) fn register_gesture(&mut self, hook: Arc<GestureHook>) {
+        self.gesture.push(hook);
     }
 }
 
@@ -51,12 +70,19 @@ impl HookStorage for HotkeyStorage {
     type ButtonHook = ButtonHook;
     type MouseCursorHook = Arc<MouseHook<CursorEvent>>;
     type MouseWheelHook = Arc<MouseHook<WheelEvent>>;
+    type GestureHook = Arc<GestureHook>;
 
     fn fetch_button_hook<S: ButtonState>(&self, event: ButtonEvent, state: &S) -> Vec<ButtonHook> {
-        let remap_hook = self
-            .remap
-            .get(&event.target)
-            .and_then(|hooks| hooks.iter().find(|hook| hook.is_executable(state)));
+        let mut remap_hooks = self.remap.get(&event.target).into_iter().flatten();
+        // The condition may no longer hold, e.g. the output of the remap changes the modifiers,
+        // but the repeats and the release must reach the remap that the press was sent to.
+        let held_hook = remap_hooks.clone().find(|hook| hook.is_held(event.target));
+        let remap_hook = match event.action {
+            ButtonAction::Press => {
+                held_hook.or_else(|| remap_hooks.find(|hook| hook.is_executable(state)))
+            }
+            ButtonAction::Release => held_hook,
+        };
         if let Some(hook) = remap_hook {
             let hook = ButtonHook::from(Arc::clone(hook));
             return vec![hook];
@@ -83,11 +109,46 @@ impl HookStorage for HotkeyStorage {
         Self::fetch_mouse_hook(&self.mouse_cursor, state)
     }
 
+    fn rewrite_mouse_cursor<S: ButtonState>(
+        &self,
+        event: CursorEvent,
+        state: &S,
+    ) -> Option<(i32, i32)> {
+        self.mouse_cursor_motion
+            .iter()
+            .find(|hook| hook.is_executable(state))
+            .map(|hook| hook.apply(event.delta))
+    }
+
     fn fetch_mouse_wheel_hook<S: ButtonState>(
         &self,
-        _: WheelEvent,
+        event: WheelEvent,
         state: &S,
     ) -> Vec<Arc<MouseHook<WheelEvent>>> {
-        Self::fetch_mouse_hook(&self.mouse_wheel, state)
+        let hooks = match event.axis {
+            WheelAxis::Vertical => &self.mouse_wheel,
+            WheelAxis::Horizontal => &self.mouse_hwheel,
+        };
+        Self::fetch_mouse_hook(hooks, state)
+    }
+
+    fn gesture_threshold<S: ButtonState>(&self, trigger: Button, state: &S) -> Option<u32> {
+        self.gesture
+            .iter()
+            .find(|hook| hook.trigger() == trigger && hook.is_executable(state))
+            .map(|hook| hook.threshold())
+    }
+
+    fn fetch_gesture_hook<S: ButtonState>(
+        &self,
+        trigger: Button,
+        strokes: &[Stroke],
+        state: &S,
+    ) -> Vec<Arc<GestureHook>> {
+        self.gesture
+            .iter()
+            .filter(|hook| hook.matches(trigger, strokes) && hook.is_executable(state))
+            .map(Arc::clone)
+            .collect()
     }
 }
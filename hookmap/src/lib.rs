@@ -4,24 +4,32 @@
 //!
 //! * `us-keyboard-layout` (default): Use US keyboard layout. This changes the [`Button`] variant.
 //! * `japanese-keyboard-layout`: Use Japanese keyboard layout. This changes the [`Button`] variant.
+//! * `async`: Enable APIs that receive events with [`Future`](std::future::Future) and `Stream`.
 //!
 //! [`Button`]: crate::device::Button
 
 pub mod hotkey;
+pub mod recorder;
 pub mod utils;
+pub mod window;
 
 #[doc(hidden)]
 pub mod macros;
 
+mod gesture;
 mod hook;
 mod runtime;
 
-pub use runtime::interceptor;
+pub use hookmap_core::{event_log, output};
+pub use runtime::{error, interceptor};
 
 /// Representation of keyboard and mouse events.
 pub mod device {
     pub use hookmap_core::button::{Button, ButtonAction, ButtonKind};
-    pub use hookmap_core::event::{ButtonEvent, CursorEvent, NativeEventOperation, WheelEvent};
+    pub use hookmap_core::event::{
+        ButtonEvent, CursorEvent, Event, NativeEventOperation, WheelAxis, WheelEvent, WHEEL_DELTA,
+    };
+    pub use hookmap_core::keyboard::{type_text, type_text_recursive};
     pub use hookmap_core::mouse;
 }
 
@@ -33,7 +41,9 @@ pub mod prelude {
     pub use super::{
         device::*,
         hotkey::{Context, Hotkey},
-        interceptor::{Filter, Interceptor},
+        interceptor::{
+            CursorDirection, CursorFilter, Filter, Interceptor, WheelDirection, WheelFilter,
+        },
         utils,
     };
 }
@@ -1,5 +1,10 @@
 pub use hookmap_core::button::{Button, ButtonAction};
-pub use hookmap_core::event::ButtonEvent;
+pub use hookmap_core::event::{ButtonEvent, WheelAxis};
+
+use hookmap_core::{keyboard, mouse};
+
+use std::thread;
+use std::time::Duration;
 
 /// Emulates button input.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -7,14 +12,81 @@ pub enum SequenceOperation {
     Click(Button),
     Press(Button),
     Release(Button),
+
+    /// Waits for the duration.
+    Sleep(Duration),
+
+    /// Waits until the button is released.
+    WaitRelease(Button),
+
+    /// Types the text regardless of the keyboard layout.
+    Text(&'static str),
+
+    /// Moves the mouse cursor to the coordinates `(x, y)`.
+    MoveTo(i32, i32),
+
+    /// Moves the mouse cursor by `(dx, dy)`.
+    MoveBy(i32, i32),
+
+    /// Rotates the mouse wheel. Positive values rotate upward.
+    Wheel(i32),
+
+    /// Scrolls along the axis in units of 1/[`WHEEL_DELTA`] of a notch.
+    /// Positive values scroll upward or rightward.
+    ///
+    /// [`WHEEL_DELTA`]: hookmap_core::event::WHEEL_DELTA
+    Scroll(WheelAxis, i32),
 }
 
 impl SequenceOperation {
+    const WAIT_RELEASE_INTERVAL: Duration = Duration::from_millis(10);
+
+    /// Returns `true` if this operation generates input.
+    fn is_input(&self) -> bool {
+        matches!(
+            self,
+            SequenceOperation::Click(_)
+                | SequenceOperation::Press(_)
+                | SequenceOperation::Release(_)
+                | SequenceOperation::Text(_)
+                | SequenceOperation::MoveTo(..)
+                | SequenceOperation::MoveBy(..)
+                | SequenceOperation::Wheel(_)
+                | SequenceOperation::Scroll(..)
+        )
+    }
+
+    /// Returns `true` if this operation only waits.
+    fn is_wait(&self) -> bool {
+        matches!(
+            self,
+            SequenceOperation::Sleep(_) | SequenceOperation::WaitRelease(_)
+        )
+    }
+
+    fn wait(&self) {
+        match self {
+            SequenceOperation::Sleep(duration) => thread::sleep(*duration),
+            SequenceOperation::WaitRelease(button) => {
+                while button.is_pressed() {
+                    thread::sleep(Self::WAIT_RELEASE_INTERVAL);
+                }
+            }
+            _ => {}
+        }
+    }
+
     fn operate(&self) {
         match self {
             SequenceOperation::Click(button) => button.click(),
             SequenceOperation::Press(button) => button.press(),
             SequenceOperation::Release(button) => button.release(),
+            SequenceOperation::Text(text) => keyboard::type_text(text),
+            SequenceOperation::MoveTo(x, y) => mouse::move_absolute(*x, *y),
+            SequenceOperation::MoveBy(dx, dy) => mouse::move_relative(*dx, *dy),
+            SequenceOperation::Wheel(speed) => mouse::rotate(*speed),
+            SequenceOperation::Scroll(axis, delta) => mouse::rotate_hi_res(*axis, *delta),
+            _ => self.wait(),
         }
     }
 
@@ -23,6 +95,12 @@ impl SequenceOperation {
             SequenceOperation::Click(button) => button.click_recursive(),
             SequenceOperation::Press(button) => button.press_recursive(),
             SequenceOperation::Release(button) => button.release_recursive(),
+            SequenceOperation::Text(text) => keyboard::type_text_recursive(text),
+            SequenceOperation::MoveTo(x, y) => mouse::move_absolute_recursive(*x, *y),
+            SequenceOperation::MoveBy(dx, dy) => mouse::move_relative_recursive(*dx, *dy),
+            SequenceOperation::Wheel(speed) => mouse::rotate_recursive(*speed),
+            SequenceOperation::Scroll(axis, delta) => mouse::rotate_hi_res_recursive(*axis, *delta),
+            _ => self.wait(),
         }
     }
 }
@@ -31,6 +109,7 @@ impl SequenceOperation {
 pub struct Sequence {
     with: Vec<Button>,
     seq: Vec<SequenceOperation>,
+    interval: Option<Duration>,
 }
 
 impl Sequence {
@@ -46,30 +125,85 @@ impl Sequence {
     ];
 
     pub fn new(with: Vec<Button>, seq: Vec<SequenceOperation>) -> Self {
-        Self { with, seq }
+        Self {
+            with,
+            seq,
+            interval: None,
+        }
     }
 
-    fn operate_with_keys(&self, operation: fn(Button)) {
-        self.with.iter().copied().for_each(operation);
+    /// Sets the time to wait between inputs.
+    /// Some applications drop inputs sent too fast.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use hookmap::*;
+    /// use std::time::Duration;
+    ///
+    /// seq!(H, E, L, L, O).interval(Duration::from_millis(20)).send();
+    /// ```
+    ///
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Returns a function that waits for the interval before each input except the first.
+    fn pacer(&self) -> impl FnMut(bool) {
+        let interval = self.interval;
+        let mut has_input = false;
+        move |is_input| {
+            if is_input {
+                if let (true, Some(interval)) = (has_input, interval) {
+                    thread::sleep(interval);
+                }
+                has_input = true;
+            }
+        }
     }
 
     fn send_inner(
         &self,
+        with: &[Button],
         press: fn(Button),
         release: fn(Button),
         operation: fn(&SequenceOperation),
     ) {
-        self.operate_with_keys(press);
-        self.seq.iter().for_each(operation);
-        self.operate_with_keys(release);
+        if self.interval.is_none() {
+            with.iter().copied().for_each(press);
+            self.seq.iter().for_each(operation);
+            with.iter().copied().for_each(release);
+            return;
+        }
+
+        let mut pause = self.pacer();
+        for &button in with {
+            pause(true);
+            press(button);
+        }
+        for op in &self.seq {
+            pause(op.is_input());
+            operation(op);
+        }
+        for &button in with {
+            pause(true);
+            release(button);
+        }
     }
 
     pub fn send(&self) {
-        self.send_inner(Button::press, Button::release, SequenceOperation::operate);
+        self.send_inner(
+            &self.with,
+            Button::press,
+            Button::release,
+            SequenceOperation::operate,
+        );
     }
 
     pub fn send_recursive(&self) {
         self.send_inner(
+            &self.with,
             Button::press_recursive,
             Button::release_recursive,
             SequenceOperation::operate_recursive,
@@ -82,15 +216,30 @@ impl Sequence {
         release: fn(Button),
         operation: fn(&SequenceOperation),
     ) {
-        let pressed_modifiers: Vec<_> = Self::MODIFIER_LIST
-            .iter()
-            .copied()
-            .filter(|button| button.is_pressed())
-            .collect();
+        // Modifiers pressed by the sequence itself are not ignored.
+        let mut held: Vec<Button> = Vec::new();
+        let mut pause = self.pacer();
 
-        pressed_modifiers.iter().copied().for_each(release);
-        self.send_inner(press, release, operation);
-        pressed_modifiers.iter().copied().for_each(press);
+        // The modifiers may be changed while sleeping, so the plan is made for each step.
+        for op in &self.seq {
+            if op.is_wait() {
+                op.wait();
+                continue;
+            }
+            pause(op.is_input());
+            let pressed: Vec<_> = Self::MODIFIER_LIST
+                .iter()
+                .copied()
+                .filter(|button| button.is_pressed() && !held.contains(button))
+                .collect();
+            ModifierPlan::new(&pressed, &self.with).apply(press, release, || operation(op));
+
+            match *op {
+                SequenceOperation::Press(button) => held.push(button),
+                SequenceOperation::Release(button) => held.retain(|&b| b != button),
+                _ => {}
+            }
+        }
     }
 
     pub fn send_ignore_modifiers(&self) {
@@ -110,6 +259,108 @@ impl Sequence {
     }
 }
 
+/// The minimal modifier changes needed to send a step of a [`Sequence`]
+/// while ignoring the pressed modifiers.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ModifierPlan {
+    /// Pressed modifiers to release before sending and to restore afterwards.
+    release: Vec<Button>,
+
+    /// Keys to press before sending and to release afterwards.
+    press: Vec<Button>,
+
+    /// A key to tap before releasing the modifiers, which is not one of the pressed modifiers.
+    /// Releasing Alt or Super alone opens the menu bar or the start menu.
+    mask: Option<Button>,
+}
+
+impl ModifierPlan {
+    const MASK_KEYS: [Button; 2] = [Button::LCtrl, Button::RCtrl];
+
+    fn new(pressed: &[Button], with: &[Button]) -> Self {
+        let release: Vec<_> = pressed
+            .iter()
+            .copied()
+            .filter(|&modifier| !with.iter().any(|&button| covers(button, modifier)))
+            .collect();
+        let press = with
+            .iter()
+            .copied()
+            .filter(|&button| {
+                !pressed
+                    .iter()
+                    .any(|&modifier| !release.contains(&modifier) && covers(button, modifier))
+            })
+            .map(|button| match button {
+                Button::Shift => Button::LShift,
+                Button::Ctrl => Button::LCtrl,
+                Button::Alt => Button::LAlt,
+                Button::Super => Button::LSuper,
+                button => button,
+            })
+            .collect();
+        // While both Ctrl keys are held, Alt and Super are not released alone.
+        let mask = release
+            .iter()
+            .any(|button| {
+                matches!(
+                    button,
+                    Button::LAlt | Button::RAlt | Button::LSuper | Button::RSuper
+                )
+            })
+            .then(|| {
+                Self::MASK_KEYS
+                    .iter()
+                    .copied()
+                    .find(|key| !pressed.contains(key))
+            })
+            .flatten();
+
+        Self {
+            release,
+            press,
+            mask,
+        }
+    }
+
+    fn apply(&self, press: fn(Button), release: fn(Button), send: impl FnOnce()) {
+        if let Some(mask) = self.mask {
+            press(mask);
+            release(mask);
+        }
+        let is_physically_pressed: Vec<_> = self
+            .release
+            .iter()
+            .map(|button| button.is_physically_pressed())
+            .collect();
+        self.release.iter().copied().for_each(release);
+        self.press.iter().copied().for_each(press);
+        send();
+        self.press.iter().copied().for_each(release);
+
+        // Modifiers held by this library, such as the outputs of remaps, are always restored,
+        // but those released by the user in the meantime are not.
+        self.release
+            .iter()
+            .zip(is_physically_pressed)
+            .filter(|&(button, was_pressed)| !was_pressed || button.is_physically_pressed())
+            .for_each(|(&button, _)| press(button));
+    }
+}
+
+/// Returns `true` if pressing `modifier` satisfies `button`.
+/// Generic modifiers such as [`Button::Shift`] are satisfied by either side.
+fn covers(button: Button, modifier: Button) -> bool {
+    button == modifier
+        || matches!(
+            (button, modifier),
+            (Button::Shift, Button::LShift | Button::RShift)
+                | (Button::Ctrl, Button::LCtrl | Button::RCtrl)
+                | (Button::Alt, Button::LAlt | Button::RAlt)
+                | (Button::Super, Button::LSuper | Button::RSuper)
+        )
+}
+
 /// Sends keyboard input.
 ///
 /// # Examples
@@ -134,6 +385,37 @@ impl Sequence {
 /// seq!(LShift down, LCtrl down, Tab, LShift up, LCtrl up).send(); // equals to above
 /// ```
 ///
+/// Use `*` to repeat clicks.
+///
+/// ```no_run
+/// use hookmap::*;
+/// seq!(DownArrow * 5, Enter).send();
+///
+/// let count = 3;
+/// seq!(Tab * count).send();
+/// ```
+///
+/// String literals are typed as text regardless of the keyboard layout.
+///
+/// ```no_run
+/// use hookmap::*;
+/// seq!("Ünïcødé 🦀", Enter).send();
+/// ```
+///
+/// Use `move_to(x, y)`, `move_by(dx, dy)`, `wheel(speed)` and `hwheel(speed)` to operate the mouse.
+///
+/// ```no_run
+/// use hookmap::*;
+/// seq!(move_to(100, 200), LeftButton, wheel(-3), hwheel(1), move_by(0, 50)).send();
+/// ```
+///
+/// Use `sleep(milliseconds)` to wait and `wait_release(...)` to wait until the key is released.
+///
+/// ```no_run
+/// use hookmap::*;
+/// seq!(wait_release(LCtrl), LAlt down, Tab, sleep(100), Tab, LAlt up).send();
+/// ```
+///
 #[macro_export]
 macro_rules! seq {
     (@with $($modifier:tt),*) => {
@@ -144,18 +426,97 @@ macro_rules! seq {
         $crate::macros::sequence::SequenceOperation::$op($crate::button_name!($button))
     };
 
-    (@button $parsed:tt) => {
-        vec!$parsed
+    // Digits are button names, not text.
+    (@click 0) => { $crate::seq!(@single 0 Click) };
+    (@click 1) => { $crate::seq!(@single 1 Click) };
+    (@click 2) => { $crate::seq!(@single 2 Click) };
+    (@click 3) => { $crate::seq!(@single 3 Click) };
+    (@click 4) => { $crate::seq!(@single 4 Click) };
+    (@click 5) => { $crate::seq!(@single 5 Click) };
+    (@click 6) => { $crate::seq!(@single 6 Click) };
+    (@click 7) => { $crate::seq!(@single 7 Click) };
+    (@click 8) => { $crate::seq!(@single 8 Click) };
+    (@click 9) => { $crate::seq!(@single 9 Click) };
+
+    (@click $text:literal) => {
+        $crate::macros::sequence::SequenceOperation::Text($text)
+    };
+
+    (@click $button:tt) => {
+        $crate::seq!(@single $button Click)
+    };
+
+    (@button [ $($parsed:tt),* ]) => {
+        <[::std::vec::Vec<$crate::macros::sequence::SequenceOperation>]>::concat(&[ $($parsed),* ])
     };
 
     (@button $parsed:tt , $($rest:tt)*) => {
         $crate::seq!(@button $parsed $($rest)*)
     };
 
+    (@button [ $($parsed:tt),* ] sleep($millis:expr) $($rest:tt)*) => {
+        $crate::seq!(
+            @button
+            [
+                $($parsed,)*
+                (vec![$crate::macros::sequence::SequenceOperation::Sleep(
+                    ::std::time::Duration::from_millis($millis)
+                )])
+            ]
+            $($rest)*
+        )
+    };
+
+    (@button [ $($parsed:tt),* ] move_to($x:expr, $y:expr) $($rest:tt)*) => {
+        $crate::seq!(
+            @button
+            [ $($parsed,)* (vec![$crate::macros::sequence::SequenceOperation::MoveTo($x, $y)]) ]
+            $($rest)*
+        )
+    };
+
+    (@button [ $($parsed:tt),* ] move_by($dx:expr, $dy:expr) $($rest:tt)*) => {
+        $crate::seq!(
+            @button
+            [ $($parsed,)* (vec![$crate::macros::sequence::SequenceOperation::MoveBy($dx, $dy)]) ]
+            $($rest)*
+        )
+    };
+
+    (@button [ $($parsed:tt),* ] wheel($speed:expr) $($rest:tt)*) => {
+        $crate::seq!(
+            @button
+            [ $($parsed,)* (vec![$crate::macros::sequence::SequenceOperation::Wheel($speed)]) ]
+            $($rest)*
+        )
+    };
+
+    (@button [ $($parsed:tt),* ] hwheel($speed:expr) $($rest:tt)*) => {
+        $crate::seq!(
+            @button
+            [
+                $($parsed,)*
+                (vec![$crate::macros::sequence::SequenceOperation::Scroll(
+                    $crate::macros::sequence::WheelAxis::Horizontal,
+                    ($speed) * $crate::device::WHEEL_DELTA,
+                )])
+            ]
+            $($rest)*
+        )
+    };
+
+    (@button [ $($parsed:tt),* ] wait_release($button:tt) $($rest:tt)*) => {
+        $crate::seq!(
+            @button
+            [ $($parsed,)* (vec![$crate::seq!(@single $button WaitRelease)]) ]
+            $($rest)*
+        )
+    };
+
     (@button [ $($parsed:tt),* ] $button:tt up $($rest:tt)*) => {
         $crate::seq!(
             @button
-            [ $($parsed,)* ($crate::seq!(@single $button Release)) ]
+            [ $($parsed,)* (vec![$crate::seq!(@single $button Release)]) ]
             $($rest)*
         )
     };
@@ -163,7 +524,15 @@ macro_rules! seq {
     (@button [ $($parsed:tt),* ] $button:tt down $($rest:tt)*) => {
         $crate::seq!(
             @button
-            [ $($parsed,)* ($crate::seq!(@single $button Press)) ]
+            [ $($parsed,)* (vec![$crate::seq!(@single $button Press)]) ]
+            $($rest)*
+        )
+    };
+
+    (@button [ $($parsed:tt),* ] $button:tt * $count:tt $($rest:tt)*) => {
+        $crate::seq!(
+            @button
+            [ $($parsed,)* (vec![$crate::seq!(@click $button); $count]) ]
             $($rest)*
         )
     };
@@ -171,7 +540,7 @@ macro_rules! seq {
     (@button [ $($parsed:tt),* ] $button:tt $($rest:tt)*) => {
         $crate::seq!(
             @button
-            [ $($parsed,)* ($crate::seq!(@single $button Click)) ]
+            [ $($parsed,)* (vec![$crate::seq!(@click $button)]) ]
             $($rest)*
         )
     };
@@ -205,9 +574,33 @@ pub const MODIFIER_LIST: [Button; 8] = [
 
 #[cfg(test)]
 mod tests {
-    use super::{Sequence, SequenceOperation};
+    use super::{ModifierPlan, Sequence, SequenceOperation, WheelAxis};
     use crate::button_name;
     use crate::device::Button;
+    use hookmap_core::output::{self, Output, OutputBackend};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    const INTERVAL: Duration = Duration::from_millis(20);
+
+    /// Records the time at which each input is sent.
+    #[derive(Debug, Default, Clone)]
+    struct Timeline(Arc<Mutex<Vec<Instant>>>);
+
+    impl OutputBackend for Timeline {
+        fn send(&self, _: Output) {
+            self.0.lock().unwrap().push(Instant::now());
+        }
+    }
+
+    fn send_times(seq: Sequence) -> Vec<Instant> {
+        let timeline = Timeline::default();
+        let redirect = output::redirect(timeline.clone());
+        seq.interval(INTERVAL).send();
+        drop(redirect);
+        let times = timeline.0.lock().unwrap().clone();
+        times
+    }
 
     #[test]
     fn button_name_macro() {
@@ -249,4 +642,161 @@ mod tests {
             Sequence::new(vec![Button::A, Button::B], vec![Release(Button::C)])
         );
     }
+
+    #[test]
+    fn seq_macro_with_repetition_and_waits() {
+        use SequenceOperation::{Click, Release, Sleep, WaitRelease};
+        assert_eq!(
+            seq!(A * 3),
+            Sequence::new(vec![], vec![Click(Button::A); 3])
+        );
+        let count = 2;
+        assert_eq!(
+            seq!(A * count, B up),
+            Sequence::new(
+                vec![],
+                vec![Click(Button::A), Click(Button::A), Release(Button::B)]
+            )
+        );
+        assert_eq!(
+            seq!(wait_release(LCtrl), A, sleep(100), B),
+            Sequence::new(
+                vec![],
+                vec![
+                    WaitRelease(Button::LCtrl),
+                    Click(Button::A),
+                    Sleep(Duration::from_millis(100)),
+                    Click(Button::B),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn seq_macro_with_text() {
+        use SequenceOperation::{Click, Text};
+        assert_eq!(
+            seq!("héllo", 0, Enter),
+            Sequence::new(
+                vec![],
+                vec![Text("héllo"), Click(Button::Key0), Click(Button::Enter)]
+            )
+        );
+        assert_eq!(
+            seq!("ab" * 2),
+            Sequence::new(vec![], vec![Text("ab"), Text("ab")])
+        );
+    }
+
+    #[test]
+    fn seq_macro_with_mouse_operations() {
+        use SequenceOperation::{Click, MoveBy, MoveTo, Scroll, Wheel};
+        let y = 200;
+        assert_eq!(
+            seq!(
+                move_to(100, y),
+                LeftButton,
+                wheel(-3),
+                hwheel(2),
+                move_by(0, -50)
+            ),
+            Sequence::new(
+                vec![],
+                vec![
+                    MoveTo(100, y),
+                    Click(Button::LeftButton),
+                    Wheel(-3),
+                    Scroll(WheelAxis::Horizontal, 240),
+                    MoveBy(0, -50),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn interval_is_waited_before_mouse_operations() {
+        let times = send_times(seq!(move_to(100, 200), LeftButton, move_by(0, 1), wheel(1)));
+
+        // The click is sent as a press and a release without the interval between them.
+        assert_eq!(times.len(), 5);
+        assert!(times[1] - times[0] >= INTERVAL);
+        assert!(times[3] - times[2] >= INTERVAL);
+        assert!(times[4] - times[3] >= INTERVAL);
+    }
+
+    #[test]
+    fn interval_is_waited_before_text() {
+        let times = send_times(seq!(A, "bc"));
+
+        // The characters of the text are typed without the interval between them.
+        assert_eq!(times.len(), 4);
+        assert!(times[2] - times[1] >= INTERVAL);
+    }
+
+    #[test]
+    fn interval_is_waited_before_scroll() {
+        let times = send_times(seq!(hwheel(1), hwheel(2)));
+
+        assert_eq!(times.len(), 2);
+        assert!(times[1] - times[0] >= INTERVAL);
+    }
+
+    #[test]
+    fn modifier_plan_keeps_required_modifiers() {
+        let plan = ModifierPlan::new(&[Button::LShift], &[Button::Ctrl]);
+        assert_eq!(
+            plan,
+            ModifierPlan {
+                release: vec![Button::LShift],
+                press: vec![Button::LCtrl],
+                mask: None,
+            }
+        );
+
+        let plan = ModifierPlan::new(&[Button::RCtrl, Button::LShift], &[Button::Ctrl]);
+        assert_eq!(
+            plan,
+            ModifierPlan {
+                release: vec![Button::LShift],
+                press: vec![],
+                mask: None,
+            }
+        );
+
+        let plan = ModifierPlan::new(&[Button::RCtrl], &[Button::LCtrl]);
+        assert_eq!(plan.release, vec![Button::RCtrl]);
+        assert_eq!(plan.press, vec![Button::LCtrl]);
+    }
+
+    #[test]
+    fn modifier_plan_masks_alt_and_super() {
+        assert_eq!(
+            ModifierPlan::new(&[Button::LAlt], &[]).mask,
+            Some(Button::LCtrl)
+        );
+        assert_eq!(
+            ModifierPlan::new(&[Button::RSuper], &[Button::Shift]).mask,
+            Some(Button::LCtrl)
+        );
+        assert_eq!(
+            ModifierPlan::new(&[Button::LAlt], &[Button::Alt]).mask,
+            None
+        );
+        assert_eq!(ModifierPlan::new(&[Button::LShift], &[]).mask, None);
+
+        // The held Ctrl must not be released by the mask.
+        let plan = ModifierPlan::new(&[Button::LCtrl, Button::LAlt], &[Button::Ctrl]);
+        assert_eq!(
+            plan,
+            ModifierPlan {
+                release: vec![Button::LAlt],
+                press: vec![],
+                mask: Some(Button::RCtrl),
+            }
+        );
+        assert_eq!(
+            ModifierPlan::new(&[Button::LCtrl, Button::RCtrl, Button::RAlt], &[]).mask,
+            None
+        );
+    }
 }
@@ -0,0 +1,495 @@
+//! Records input events and replays them later.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use hookmap::prelude::*;
+//! use hookmap::recorder::Recorder;
+//! use std::{thread, time::Duration};
+//!
+//! let handle = Recorder::new().ignore_injected(true).start();
+//! thread::sleep(Duration::from_secs(5));
+//! let recording = handle.stop();
+//!
+//! // Replays the recording when `F1` is pressed, and twice as fast when `F2` is pressed.
+//! let mut hotkey = Hotkey::new();
+//! hotkey
+//!     .register(Context::new())
+//!     .on_press(Button::F1, recording.clone())
+//!     .on_press(Button::F2, move |_| recording.replay_with_speed(2.0));
+//! hotkey.install();
+//! ```
+//!
+
+use hookmap_core::button::ButtonAction;
+use hookmap_core::event::{ButtonEvent, Event, WheelAxis, WheelEvent, WHEEL_DELTA};
+use hookmap_core::mouse;
+
+use crate::interceptor::{CursorFilter, EventFilter, Filter, Interceptor, WheelFilter};
+use crate::macros::sequence::{Sequence, SequenceOperation};
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+type Captured = Arc<Mutex<Vec<(Instant, Event)>>>;
+
+/// Records button, mouse wheel and mouse cursor events.
+#[derive(Debug, Default, Clone)]
+pub struct Recorder {
+    ignore_injected: bool,
+}
+
+impl Recorder {
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Creates a new recorder that records all events, including injected ones.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to ignore events generated by this library, such as inputs sent by hotkeys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::recorder::Recorder;
+    ///
+    /// let recorder = Recorder::new().ignore_injected(true);
+    /// ```
+    ///
+    pub fn ignore_injected(mut self, ignore: bool) -> Self {
+        self.ignore_injected = ignore;
+        self
+    }
+
+    /// Starts recording. Recording continues until [`RecorderHandle::stop`] is called.
+    ///
+    /// Recorded events are not blocked.
+    pub fn start(&self) -> RecorderHandle {
+        let start = Instant::now();
+        let stop = Arc::new(AtomicBool::new(false));
+        let captured = Captured::default();
+
+        let mut filter = Filter::new();
+        let mut wheel_filter = WheelFilter::new().any_axis();
+        let mut cursor_filter = CursorFilter::new();
+        if self.ignore_injected {
+            filter = filter.injected(false);
+            wheel_filter = wheel_filter.callback(|e| !e.injected);
+            cursor_filter = cursor_filter.callback(|e| !e.injected);
+        }
+
+        let threads = vec![
+            Self::record(filter, Event::Button, &stop, &captured),
+            Self::record(wheel_filter, Event::Wheel, &stop, &captured),
+            Self::record(cursor_filter, Event::Cursor, &stop, &captured),
+        ];
+
+        RecorderHandle {
+            start,
+            stop,
+            captured,
+            threads,
+        }
+    }
+
+    fn record<F: EventFilter>(
+        filter: F,
+        wrap: fn(F::Event) -> Event,
+        stop: &Arc<AtomicBool>,
+        captured: &Captured,
+    ) -> JoinHandle<()> {
+        // Subscribes before spawning the thread so that no event is missed after `start` returns.
+        let mut iter = Interceptor::dispatch(filter).iter();
+        let stop = Arc::clone(stop);
+        let captured = Arc::clone(captured);
+
+        thread::spawn(move || {
+            while !stop.load(Ordering::SeqCst) {
+                // The time of capture is used, since this thread may be delayed.
+                if let Some((instant, event)) = iter.next_timeout_with_time(Self::POLL_INTERVAL) {
+                    captured
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .push((instant, wrap(event)));
+                }
+            }
+        })
+    }
+}
+
+/// A recording in progress, created by [`Recorder::start`].
+///
+/// Recording stops when this is dropped.
+#[derive(Debug)]
+pub struct RecorderHandle {
+    start: Instant,
+    stop: Arc<AtomicBool>,
+    captured: Captured,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl RecorderHandle {
+    fn join(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        for thread in self.threads.drain(..) {
+            // The thread only panics if the runtime panics.
+            let _ = thread.join();
+        }
+    }
+
+    /// Stops recording and returns the recorded events.
+    pub fn stop(mut self) -> Recording {
+        self.join();
+
+        let mut captured =
+            std::mem::take(&mut *self.captured.lock().unwrap_or_else(PoisonError::into_inner));
+        captured.sort_by_key(|&(instant, _)| instant);
+
+        let mut prev = self.start;
+        let steps = captured
+            .into_iter()
+            .map(|(instant, event)| {
+                let delay = instant.saturating_duration_since(prev);
+                prev = instant;
+                Step { delay, event }
+            })
+            .collect();
+        Recording::new(steps)
+    }
+}
+
+impl Drop for RecorderHandle {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+/// A recorded event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Step {
+    /// Time elapsed since the previous step, or since the start of the recording.
+    pub delay: Duration,
+
+    /// The recorded event.
+    pub event: Event,
+}
+
+/// Events recorded by [`Recorder`].
+///
+/// This can be converted to and from a text with [`Display`] and [`FromStr`].
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::recorder::Recording;
+///
+/// let text = "hookmap-recording 3\n0 press A\n50000 release A\n";
+/// let recording: Recording = text.parse().unwrap();
+/// assert_eq!(recording.to_string(), text);
+/// ```
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Recording {
+    steps: Vec<Step>,
+}
+
+impl Recording {
+    const HEADER: &'static str = "hookmap-recording";
+    const VERSION: u32 = 3;
+
+    /// Creates a recording that replays `steps` in order.
+    pub fn new(steps: Vec<Step>) -> Self {
+        Self { steps }
+    }
+
+    /// Returns the recorded steps in order.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Returns the steps to edit the recording.
+    pub fn steps_mut(&mut self) -> &mut Vec<Step> {
+        &mut self.steps
+    }
+
+    /// Converts the recording into the recorded steps.
+    pub fn into_steps(self) -> Vec<Step> {
+        self.steps
+    }
+
+    /// Returns the time it takes to replay this recording at the original speed.
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(|step| step.delay).sum()
+    }
+
+    /// Replays the recording at the original speed.
+    pub fn replay(&self) {
+        self.replay_with_speed(1.0);
+    }
+
+    /// Replays the recording `speed` times as fast as the original.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed` is not a positive finite number.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use hookmap::recorder::Recording;
+    ///
+    /// let recording: Recording = "hookmap-recording 3\n0 press A\n50000 release A\n"
+    ///     .parse()
+    ///     .unwrap();
+    /// recording.replay_with_speed(0.5);
+    /// ```
+    ///
+    pub fn replay_with_speed(&self, speed: f64) {
+        assert!(
+            speed.is_finite() && speed > 0.0,
+            "The speed must be a positive finite number."
+        );
+        for step in &self.steps {
+            thread::sleep(step.delay.div_f64(speed));
+            match step.event {
+                Event::Button(event) => match event.action {
+                    ButtonAction::Press => event.target.press(),
+                    ButtonAction::Release => event.target.release(),
+                },
+                Event::Wheel(event) => mouse::rotate_hi_res(event.axis, event.hi_res_delta),
+                Event::Cursor(event) => mouse::move_relative(event.delta.0, event.delta.1),
+            }
+        }
+    }
+
+    /// Converts the recording to a [`Sequence`] that waits between the events.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use hookmap::recorder::Recording;
+    ///
+    /// let recording: Recording = "hookmap-recording 3\n0 press A\n50000 release A\n"
+    ///     .parse()
+    ///     .unwrap();
+    /// recording.to_sequence().send();
+    /// ```
+    ///
+    pub fn to_sequence(&self) -> Sequence {
+        let mut seq = Vec::with_capacity(self.steps.len() * 2);
+        for step in &self.steps {
+            if !step.delay.is_zero() {
+                seq.push(SequenceOperation::Sleep(step.delay));
+            }
+            seq.push(match step.event {
+                Event::Button(ButtonEvent {
+                    target,
+                    action: ButtonAction::Press,
+                    ..
+                }) => SequenceOperation::Press(target),
+                Event::Button(ButtonEvent {
+                    target,
+                    action: ButtonAction::Release,
+                    ..
+                }) => SequenceOperation::Release(target),
+                Event::Wheel(WheelEvent {
+                    delta,
+                    hi_res_delta,
+                    axis: WheelAxis::Vertical,
+                    ..
+                }) if hi_res_delta == delta * WHEEL_DELTA => SequenceOperation::Wheel(delta),
+                Event::Wheel(event) => SequenceOperation::Scroll(event.axis, event.hi_res_delta),
+                Event::Cursor(event) => SequenceOperation::MoveBy(event.delta.0, event.delta.1),
+            });
+        }
+        Sequence::new(vec![], seq)
+    }
+}
+
+impl Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", Self::HEADER, Self::VERSION)?;
+        for step in &self.steps {
+            writeln!(f, "{} {}", step.delay.as_micros(), step.event)?;
+        }
+        Ok(())
+    }
+}
+
+/// An error returned when parsing a [`Recording`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRecordingError {
+    line: usize,
+    message: String,
+}
+
+impl ParseRecordingError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the 1-based line number where the error occurred.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl Display for ParseRecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseRecordingError {}
+
+fn parse_step(text: &str, line: usize) -> Result<Step, ParseRecordingError> {
+    let (delay, event) = text.split_once(' ').unwrap_or((text, ""));
+    let delay = delay
+        .parse()
+        .map(Duration::from_micros)
+        .map_err(|_| ParseRecordingError::new(line, format!("invalid delay: `{}`", delay)))?;
+    let event = event
+        .parse()
+        .map_err(|e| ParseRecordingError::new(line, format!("{}", e)))?;
+    Ok(Step { delay, event })
+}
+
+impl FromStr for Recording {
+    type Err = ParseRecordingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate().map(|(i, text)| (i + 1, text.trim()));
+
+        // Recordings of the earlier versions lack the position of cursor movements.
+        let version = lines
+            .next()
+            .and_then(|(_, text)| text.strip_prefix(Self::HEADER))
+            .and_then(|version| version.trim().parse::<u32>().ok());
+        if !matches!(version, Some(1..=Self::VERSION)) {
+            return Err(ParseRecordingError::new(
+                1,
+                format!("expected `{} {}`", Self::HEADER, Self::VERSION),
+            ));
+        }
+
+        let steps = lines
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(line, text)| parse_step(text, line))
+            .collect::<Result<_, _>>()?;
+        Ok(Recording::new(steps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hookmap_core::button::Button;
+    use hookmap_core::event::{CursorEvent, MonitorId};
+
+    fn button_step(delay: u64, target: Button, action: ButtonAction) -> Step {
+        Step {
+            delay: Duration::from_millis(delay),
+            event: Event::Button(ButtonEvent {
+                target,
+                action,
+                injected: false,
+                is_repeat: false,
+            }),
+        }
+    }
+
+    fn recording() -> Recording {
+        Recording::new(vec![
+            button_step(0, Button::LShift, ButtonAction::Press),
+            Step {
+                delay: Duration::from_millis(10),
+                event: Event::Cursor(CursorEvent {
+                    delta: (3, -2),
+                    position: (10, 20),
+                    monitor: MonitorId(1),
+                    injected: true,
+                }),
+            },
+            Step {
+                delay: Duration::ZERO,
+                event: Event::Wheel(WheelEvent {
+                    delta: -1,
+                    hi_res_delta: -120,
+                    axis: WheelAxis::Vertical,
+                    injected: false,
+                }),
+            },
+            Step {
+                delay: Duration::ZERO,
+                event: Event::Wheel(WheelEvent {
+                    delta: 0,
+                    hi_res_delta: 60,
+                    axis: WheelAxis::Horizontal,
+                    injected: false,
+                }),
+            },
+            button_step(25, Button::LShift, ButtonAction::Release),
+        ])
+    }
+
+    #[test]
+    fn recording_round_trips_through_text() {
+        let text = recording().to_string();
+        assert_eq!(
+            text,
+            "hookmap-recording 3\n\
+             0 press LShift\n\
+             10000 cursor 3 -2 at 10 20 on 1 injected\n\
+             0 wheel -1\n\
+             0 hwheel 0 hires 60\n\
+             25000 release LShift\n"
+        );
+        assert_eq!(text.parse(), Ok(recording()));
+    }
+
+    #[test]
+    fn invalid_recording_is_rejected() {
+        assert_eq!("0 press A".parse::<Recording>().unwrap_err().line(), 1);
+        assert_eq!(
+            "hookmap-recording 1\n0 press A\n\n5 press Foo"
+                .parse::<Recording>()
+                .unwrap_err()
+                .line(),
+            4
+        );
+        assert!("hookmap-recording 1\n0 wheel".parse::<Recording>().is_err());
+        assert!("hookmap-recording 1\n0 wheel 1 2"
+            .parse::<Recording>()
+            .is_err());
+        assert!("hookmap-recording 4\n".parse::<Recording>().is_err());
+    }
+
+    #[test]
+    fn recording_converts_to_timed_sequence() {
+        use SequenceOperation::{MoveBy, Press, Release, Scroll, Sleep, Wheel};
+        assert_eq!(recording().duration(), Duration::from_millis(35));
+        assert_eq!(
+            recording().to_sequence(),
+            Sequence::new(
+                vec![],
+                vec![
+                    Press(Button::LShift),
+                    Sleep(Duration::from_millis(10)),
+                    MoveBy(3, -2),
+                    Wheel(-1),
+                    Scroll(WheelAxis::Horizontal, 60),
+                    Sleep(Duration::from_millis(25)),
+                    Release(Button::LShift),
+                ]
+            )
+        );
+    }
+}
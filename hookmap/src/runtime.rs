@@ -1,13 +1,119 @@
 mod button_state;
+pub mod error;
 mod event_broker;
+mod filter;
 pub mod interceptor;
+mod queue;
+pub(crate) mod suspension;
 
-use hookmap_core::event::{Event, NativeEventHandler, NativeEventOperation};
+use hookmap_core::button::ButtonAction;
+use hookmap_core::event::{
+    ButtonEvent, CursorEvent, Event, EventReceiver, NativeEventHandler, NativeEventOperation,
+};
+use hookmap_core::event_log::{self, EventLogWriter, LogEntry};
+use hookmap_core::output::{self, Output, OutputBackend, OutputLog};
 
-use self::button_state::RealButtonState;
+pub(crate) use self::button_state::RealButtonState;
+
+use self::button_state::VirtualButtonState;
+use self::filter::{CursorFilter, EventFilter, Filter, WheelFilter};
+use crate::gesture::Recognizer;
 use crate::hook::{ButtonState, Hook, HookStorage};
 
-use std::thread;
+use std::io::Write;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Passes `event` to interceptors, and returns the native handler back if it is not
+/// blocked by them. Interceptors do not capture events while hotkeys are suspended,
+/// nor cancelled events, which can be neither blocked nor replaced.
+fn intercept<F: EventFilter>(
+    event: F::Event,
+    native_handler: NativeEventHandler,
+) -> Option<NativeEventHandler> {
+    if suspension::is_suspended() || native_handler.is_cancelled() {
+        return Some(native_handler);
+    }
+    if let Some(events) = interceptor::transform_event::<F>(event) {
+        native_handler.block();
+        // Injected on this thread to keep the order of subsequent events.
+        events.into_iter().for_each(F::inject);
+        return None;
+    }
+    match interceptor::publish_event::<F>(event) {
+        NativeEventOperation::Block => {
+            native_handler.block();
+            None
+        }
+        NativeEventOperation::Dispatch => Some(native_handler),
+    }
+}
+
+/// The result of [`Hotkey::replay`](crate::hotkey::Hotkey::replay).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayOutcome {
+    /// Whether each event is blocked or dispatched.
+    pub decisions: Vec<NativeEventOperation>,
+
+    /// The inputs sent by the hotkeys, in the order in which they were sent.
+    pub outputs: Vec<Output>,
+}
+
+/// Records the inputs sent while replaying, and reflects them in the replayed state of buttons.
+struct VirtualOutput {
+    log: OutputLog,
+    state: VirtualButtonState,
+}
+
+impl OutputBackend for VirtualOutput {
+    fn send(&self, output: Output) {
+        if let Output::Button(button, action) = output {
+            self.state.set(button, action);
+        }
+        self.log.send(output);
+    }
+}
+
+/// A handle to the hotkeys running in the background.
+///
+/// Dropping this handle uninstalls the hotkeys and releases the buttons
+/// that have been pressed by hookmap and not released yet.
+///
+/// # Examples
+///
+/// ```no_run
+/// use hookmap::prelude::*;
+///
+/// let mut hotkey = Hotkey::new();
+/// hotkey
+///     .register(Context::default())
+///     .remap(Button::A, Button::B);
+///
+/// let handle = hotkey.spawn();
+/// std::thread::sleep(std::time::Duration::from_secs(10));
+/// handle.uninstall();
+/// ```
+///
+#[derive(Debug)]
+pub struct RuntimeHandle {
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl RuntimeHandle {
+    /// Uninstalls the hotkeys and waits for the runtime to stop.
+    /// This is the same as dropping the handle.
+    pub fn uninstall(self) {}
+}
+
+impl Drop for RuntimeHandle {
+    fn drop(&mut self) {
+        if let Some(join_handle) = self.join_handle.take() {
+            hookmap_core::uninstall_hook();
+            let _ = join_handle.join();
+        }
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct Runtime<T, S: ButtonState = RealButtonState>
@@ -16,9 +122,15 @@ where
     <T as HookStorage>::ButtonHook: Send,
     <T as HookStorage>::MouseWheelHook: Send,
     <T as HookStorage>::MouseCursorHook: Send,
+    <T as HookStorage>::GestureHook: Send,
 {
     storage: T,
     state: S,
+    gesture: Arc<Mutex<Option<Recognizer>>>,
+
+    // Whether the hooks are run on the runtime thread,
+    // so that the inputs are sent in a deterministic order while replaying.
+    sequential: bool,
 }
 
 impl<T> Runtime<T, RealButtonState>
@@ -27,21 +139,156 @@ where
     <T as HookStorage>::ButtonHook: Send,
     <T as HookStorage>::MouseWheelHook: Send,
     <T as HookStorage>::MouseCursorHook: Send,
+    <T as HookStorage>::GestureHook: Send,
 {
     pub(crate) fn new(storage: T) -> Self {
         Self::with_state(storage, RealButtonState)
     }
 }
 
+impl<T> Runtime<T, VirtualButtonState>
+where
+    T: HookStorage + Send,
+    <T as HookStorage>::ButtonHook: Send,
+    <T as HookStorage>::MouseWheelHook: Send,
+    <T as HookStorage>::MouseCursorHook: Send,
+    <T as HookStorage>::GestureHook: Send,
+{
+    /// Handles the events from `event_receiver` without sending inputs to the OS,
+    /// and returns the inputs sent by the hooks.
+    pub(crate) fn run_virtually(storage: T, event_receiver: EventReceiver) -> Vec<Output> {
+        let state = VirtualButtonState::default();
+        let log = OutputLog::new();
+        let runtime = Self {
+            sequential: true,
+            ..Self::with_state(storage, state.clone())
+        };
+
+        let _redirect = output::redirect(VirtualOutput {
+            log: log.clone(),
+            state,
+        });
+        runtime.run(event_receiver);
+        log.take()
+    }
+
+    /// Handles the logged events without installing a hook and without sending inputs
+    /// to the OS, and returns the decisions made for them and the inputs sent by the hooks.
+    pub(crate) fn replay(storage: T, entries: Vec<LogEntry>) -> ReplayOutcome {
+        let (event_receiver, replay) = event_log::replay(entries);
+        let outputs = Self::run_virtually(storage, event_receiver);
+        ReplayOutcome {
+            decisions: replay.join(),
+            outputs,
+        }
+    }
+}
+
 impl<T, S: ButtonState> Runtime<T, S>
 where
     T: HookStorage + Send + 'static,
     <T as HookStorage>::ButtonHook: Send,
     <T as HookStorage>::MouseWheelHook: Send,
     <T as HookStorage>::MouseCursorHook: Send,
+    <T as HookStorage>::GestureHook: Send,
 {
     pub(crate) fn with_state(storage: T, state: S) -> Self {
-        Self { storage, state }
+        Self {
+            storage,
+            state,
+            gesture: Arc::default(),
+            sequential: false,
+        }
+    }
+
+    fn run_hooks<E, H>(&self, hooks: Vec<H>, event: E)
+    where
+        E: Copy + Send + 'static,
+        H: Hook<E> + Send + 'static,
+    {
+        if self.sequential {
+            hooks.iter().for_each(|hook| hook.run(event));
+        } else {
+            thread::spawn(move || hooks.iter().for_each(|hook| hook.run(event)));
+        }
+    }
+
+    /// Presses the trigger as usual if it is held without drawing a stroke for a while,
+    /// so that holding and dragging with it keep working.
+    fn spawn_hold_timer(&self, pressed_at: Instant) {
+        let gesture = Arc::downgrade(&self.gesture);
+        thread::spawn(move || {
+            thread::sleep(Recognizer::HOLD_TIMEOUT);
+            let gesture = match gesture.upgrade() {
+                Some(gesture) => gesture,
+                None => return,
+            };
+            // Pressed while locked, so that the release of the trigger is not handled before.
+            let mut gesture = gesture.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(recognizer) = gesture
+                .as_mut()
+                .filter(|recognizer| recognizer.pressed_at() == pressed_at)
+            {
+                if recognizer.pass_through() {
+                    recognizer.trigger().press();
+                }
+            }
+        });
+    }
+
+    /// Recognizes gestures drawn while a trigger button is held, and returns the native
+    /// handler back if `event` is not a press or release of the trigger.
+    fn handle_gesture(
+        &self,
+        event: ButtonEvent,
+        native_handler: NativeEventHandler,
+    ) -> Option<NativeEventHandler> {
+        if event.injected {
+            return Some(native_handler);
+        }
+        let mut gesture = self.gesture.lock().unwrap_or_else(PoisonError::into_inner);
+        match event.action {
+            // A cancelled press of the trigger has already been notified,
+            // so gestures are not recognized until it is released.
+            ButtonAction::Press
+                if gesture.is_none()
+                    && !suspension::is_suspended()
+                    && !native_handler.is_cancelled() =>
+            {
+                match self.storage.gesture_threshold(event.target, &self.state) {
+                    Some(threshold) => {
+                        native_handler.block();
+                        let recognizer = Recognizer::new(event.target, threshold);
+                        self.spawn_hold_timer(recognizer.pressed_at());
+                        *gesture = Some(recognizer);
+                        None
+                    }
+                    None => Some(native_handler),
+                }
+            }
+            ButtonAction::Release
+                if gesture.as_ref().map(Recognizer::trigger) == Some(event.target) =>
+            {
+                native_handler.block();
+                let recognizer = gesture.take().unwrap();
+                // Sent on this thread to keep the order of subsequent events.
+                if recognizer.is_passed_through() {
+                    event.target.release();
+                    return None;
+                }
+                let strokes = recognizer.finish();
+                if strokes.is_empty() {
+                    event.target.click();
+                } else {
+                    let hooks =
+                        self.storage
+                            .fetch_gesture_hook(event.target, &strokes, &self.state);
+                    self.run_hooks(hooks, event);
+                }
+                None
+            }
+            _ => Some(native_handler),
+        }
     }
 
     fn handle_event<F, E, H>(&self, fetch: F, event: E, native_handler: NativeEventHandler)
@@ -50,7 +297,10 @@ where
         E: Copy + Send + 'static,
         H: Hook<E> + Send + 'static,
     {
-        let hooks = fetch(&self.storage, event, &self.state);
+        let mut hooks = fetch(&self.storage, event, &self.state);
+        if suspension::is_suspended() {
+            hooks.retain(|hook| hook.is_exempt_from_suspension(event));
+        }
         let has_block_operation = hooks
             .iter()
             .map(|hook| hook.native_event_operation())
@@ -61,26 +311,89 @@ where
             NativeEventOperation::Dispatch
         };
         native_handler.handle(operation);
-        thread::spawn(move || hooks.iter().for_each(|hook| hook.run(event)));
+        hooks.iter().for_each(|hook| hook.run_in_order(event));
+        self.run_hooks(hooks, event);
     }
 
     pub(crate) fn start(&self) {
         let event_receiver = hookmap_core::install_hook();
+        self.run(event_receiver);
+    }
+
+    pub(crate) fn start_with_event_log<W>(&self, writer: EventLogWriter<W>)
+    where
+        W: Write + Send + 'static,
+    {
+        let event_receiver = event_log::capture(hookmap_core::install_hook(), writer);
+        self.run(event_receiver);
+    }
+
+    pub(crate) fn spawn(self) -> RuntimeHandle
+    where
+        S: Send + 'static,
+    {
+        let event_receiver = hookmap_core::install_hook();
+        let join_handle = thread::spawn(move || self.run(event_receiver));
+        RuntimeHandle {
+            join_handle: Some(join_handle),
+        }
+    }
 
+    fn run(&self, event_receiver: EventReceiver) {
+        // The buttons held by the hotkeys must not stay pressed if the runtime panics.
+        hookmap_core::release_injected_buttons_on_panic();
+        // A cancelled event is handled as usual, except that the decision on it has no effect,
+        // so that the state of buttons, held remaps and gestures keep up with it.
         while let Ok((event, native_handler)) = event_receiver.recv() {
             match event {
                 Event::Button(event) => {
-                    if interceptor::publish_event(event) == NativeEventOperation::Block {
-                        native_handler.block();
-                        continue;
+                    self.state.update(event);
+                    let native_handler = intercept::<Filter>(event, native_handler)
+                        .and_then(|native_handler| self.handle_gesture(event, native_handler));
+                    if let Some(native_handler) = native_handler {
+                        self.handle_event(HookStorage::fetch_button_hook, event, native_handler);
                     }
-                    self.handle_event(HookStorage::fetch_button_hook, event, native_handler);
                 }
                 Event::Wheel(event) => {
-                    self.handle_event(HookStorage::fetch_mouse_wheel_hook, event, native_handler);
+                    if let Some(native_handler) = intercept::<WheelFilter>(event, native_handler) {
+                        self.handle_event(
+                            HookStorage::fetch_mouse_wheel_hook,
+                            event,
+                            native_handler,
+                        );
+                    }
                 }
                 Event::Cursor(event) => {
-                    self.handle_event(HookStorage::fetch_mouse_cursor_hook, event, native_handler);
+                    let native_handler = match intercept::<CursorFilter>(event, native_handler) {
+                        Some(native_handler) => native_handler,
+                        None => continue,
+                    };
+                    // Movements sent by this library are not parts of gestures.
+                    if let Some(gesture) = self
+                        .gesture
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .as_mut()
+                        .filter(|_| !event.injected)
+                    {
+                        gesture.add(event.delta);
+                    }
+                    // A cancelled movement has already moved the cursor.
+                    let rewritten = (!suspension::is_suspended() && !native_handler.is_cancelled())
+                        .then(|| self.storage.rewrite_mouse_cursor(event, &self.state))
+                        .flatten();
+                    match rewritten {
+                        Some(delta) => {
+                            native_handler.block();
+                            // Moved on this thread to keep the order of subsequent events.
+                            CursorFilter::inject(CursorEvent { delta, ..event });
+                        }
+                        None => self.handle_event(
+                            HookStorage::fetch_mouse_cursor_hook,
+                            event,
+                            native_handler,
+                        ),
+                    }
                 }
             }
         }
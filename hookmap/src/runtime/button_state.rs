@@ -1,7 +1,11 @@
-use hookmap_core::button::Button;
+use hookmap_core::button::{Button, ButtonAction};
+use hookmap_core::event::ButtonEvent;
 
 use crate::hook::ButtonState;
 
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, PoisonError};
+
 #[derive(Debug, Default)]
 pub(crate) struct RealButtonState;
 
@@ -14,3 +18,40 @@ impl ButtonState for RealButtonState {
         button.is_released()
     }
 }
+
+/// The state of buttons changed only by replayed events and redirected inputs.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct VirtualButtonState(Arc<Mutex<HashSet<Button>>>);
+
+impl VirtualButtonState {
+    pub(crate) fn set(&self, button: Button, action: ButtonAction) {
+        let mut pressed = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        match action {
+            ButtonAction::Press => pressed.insert(button),
+            ButtonAction::Release => pressed.remove(&button),
+        };
+    }
+}
+
+impl ButtonState for VirtualButtonState {
+    fn is_pressed(&self, button: Button) -> bool {
+        let pressed = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        // Generic modifiers such as `Button::Shift` are pressed if either side of them is pressed.
+        let sides = match button {
+            Button::Shift => [Button::LShift, Button::RShift],
+            Button::Ctrl => [Button::LCtrl, Button::RCtrl],
+            Button::Alt => [Button::LAlt, Button::RAlt],
+            Button::Super => [Button::LSuper, Button::RSuper],
+            _ => [button, button],
+        };
+        pressed.contains(&button) || sides.iter().any(|side| pressed.contains(side))
+    }
+
+    fn is_released(&self, button: Button) -> bool {
+        !self.is_pressed(button)
+    }
+
+    fn update(&self, event: ButtonEvent) {
+        self.set(event.target, event.action);
+    }
+}
@@ -0,0 +1,169 @@
+//! Reporting panics of user callbacks.
+//!
+//! Panics in hotkey callbacks and [`Filter`] callbacks are caught so that they do not
+//! stop the hotkeys. Caught panics are passed to the handler set by [`set_handler`].
+//! By default, they are printed to the standard error.
+//!
+//! [`Filter`]: crate::interceptor::Filter
+//!
+//! # Examples
+//!
+//! ```
+//! use hookmap::error;
+//!
+//! error::set_handler(|panic| {
+//!     eprintln!("{:?} {:?} panicked: {}", panic.callback.kind, panic.callback.targets, panic.message);
+//! });
+//! ```
+//!
+
+use hookmap_core::button::Button;
+
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use once_cell::sync::Lazy;
+
+/// Kind of user callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CallbackKind {
+    /// Registered by [`Registrar::on_press`](crate::hotkey::Registrar::on_press).
+    OnPress,
+
+    /// Registered by [`Registrar::on_release`](crate::hotkey::Registrar::on_release).
+    OnRelease,
+
+    /// Registered by [`Registrar::mouse_wheel`](crate::hotkey::Registrar::mouse_wheel).
+    MouseWheel,
+
+    /// Registered by [`Registrar::mouse_cursor`](crate::hotkey::Registrar::mouse_cursor).
+    MouseCursor,
+
+    /// Registered by [`Registrar::gesture`](crate::hotkey::Registrar::gesture).
+    Gesture,
+
+    /// Registered by [`Context::when`](crate::hotkey::Context::when).
+    Condition,
+
+    /// Registered by [`Filter::callback`](crate::interceptor::Filter::callback) and the like.
+    Filter,
+
+    /// Registered by [`Interceptor::transform`](crate::interceptor::Interceptor::transform).
+    Transform,
+}
+
+/// Identifies a user callback.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CallbackId {
+    /// Kind of the callback.
+    pub kind: CallbackKind,
+
+    /// Buttons for which the callback is registered.
+    /// This is empty if the callback is not registered for buttons.
+    pub targets: Vec<Button>,
+}
+
+impl CallbackId {
+    pub(crate) fn new(kind: CallbackKind, targets: impl IntoIterator<Item = Button>) -> Self {
+        Self {
+            kind,
+            targets: targets.into_iter().collect(),
+        }
+    }
+}
+
+/// A panic caught in a user callback.
+#[derive(Debug, Clone)]
+pub struct CallbackPanic {
+    /// The callback that panicked.
+    pub callback: CallbackId,
+
+    /// The panic message.
+    pub message: String,
+}
+
+type Handler = Arc<dyn Fn(&CallbackPanic) + Send + Sync>;
+
+static HANDLER: Lazy<RwLock<Handler>> = Lazy::new(|| RwLock::new(Arc::new(default_handler)));
+
+fn default_handler(panic: &CallbackPanic) {
+    eprintln!(
+        "hookmap: {:?} callback for {:?} panicked: {}",
+        panic.callback.kind, panic.callback.targets, panic.message
+    );
+}
+
+/// Sets the handler that is called when a user callback panics.
+///
+/// The handler is called on the thread on which the callback panicked.
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::error;
+///
+/// error::set_handler(|panic| eprintln!("{}", panic.message));
+/// ```
+///
+pub fn set_handler<F>(handler: F)
+where
+    F: Fn(&CallbackPanic) + Send + Sync + 'static,
+{
+    *HANDLER.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(handler);
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("Box<dyn Any>")
+    }
+}
+
+fn report(callback: &CallbackId, payload: &(dyn Any + Send)) {
+    let panic = CallbackPanic {
+        callback: callback.clone(),
+        message: panic_message(payload),
+    };
+    let handler = Arc::clone(&HANDLER.read().unwrap_or_else(PoisonError::into_inner));
+
+    // A panicking handler must not take down the caller either.
+    let _ = hookmap_core::catch_unwind(AssertUnwindSafe(|| handler(&panic)));
+}
+
+/// Runs `f` and reports the panic if it occurs.
+pub(crate) fn catch<R>(callback: &CallbackId, f: impl FnOnce() -> R) -> Option<R> {
+    // The buttons held by the hotkeys must not be released by a panic that is recovered from.
+    match hookmap_core::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => Some(result),
+        Err(payload) => {
+            report(callback, &*payload);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic;
+
+    #[test]
+    fn panic_message_is_extracted_from_payload() {
+        let payload = panic::catch_unwind(|| panic!("static message")).unwrap_err();
+        assert_eq!(panic_message(&*payload), "static message");
+
+        let payload = panic::catch_unwind(|| panic!("formatted {}", 1)).unwrap_err();
+        assert_eq!(panic_message(&*payload), "formatted 1");
+    }
+
+    #[test]
+    fn catch_returns_none_on_panic() {
+        let callback = CallbackId::new(CallbackKind::OnPress, [Button::A]);
+        assert_eq!(catch(&callback, || 1), Some(1));
+        assert_eq!(catch(&callback, || -> i32 { panic!() }), None);
+    }
+}
@@ -1,192 +1,187 @@
-use hookmap_core::button::{Button, ButtonAction};
-use hookmap_core::event::{ButtonEvent, NativeEventOperation};
+use hookmap_core::event::NativeEventOperation;
 
-use crate::macros::button_arg::ButtonArg;
+use super::filter::EventFilter;
+use super::queue::Queue;
 
+use std::fmt::Debug;
 use std::sync::mpsc::{self, Receiver, SyncSender};
-use std::{collections::HashSet, fmt::Debug, sync::Arc};
+use std::sync::Arc;
 
-#[derive(Debug, Clone)]
-struct Target {
-    target: Option<HashSet<Button>>,
-    ignored: Option<HashSet<Button>>,
-}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) struct SubscriptionId(u64);
 
-impl Target {
-    #[allow(clippy::needless_return)]
-    fn filter(&self, button: Button) -> bool {
-        return match &self.target {
-            Some(set) => set.contains(&button),
-            None => true,
-        } && match &self.ignored {
-            Some(set) => !set.contains(&button),
-            None => true,
-        };
-    }
+#[derive(Debug)]
+enum Sink<E> {
+    /// Receives a single event.
+    Once(SyncSender<E>),
+
+    /// Receives events until the queue is closed.
+    Persistent(Arc<Queue<E>>),
 }
 
-impl From<ButtonArg> for Target {
-    fn from(args: ButtonArg) -> Self {
-        let mut target = args.iter_plain().peekable();
-        let mut ignored = args.iter_not().peekable();
+impl<E> Sink<E> {
+    /// Returns `true` if the event is taken by the subscriber.
+    fn send(&self, event: E) -> bool {
+        match self {
+            // The receiver may have been dropped by a panicked thread.
+            Sink::Once(tx) => tx.send(event).is_ok(),
+            Sink::Persistent(queue) => queue.push(event),
+        }
+    }
 
-        Target {
-            target: target.peek().copied().map(|_| target.collect()),
-            ignored: ignored.peek().copied().map(|_| ignored.collect()),
+    fn is_reusable(&self) -> bool {
+        match self {
+            Sink::Once(_) => false,
+            Sink::Persistent(queue) => !queue.is_closed(),
         }
     }
 }
 
-#[derive(Clone)]
-struct Callback(Arc<dyn Fn(&ButtonEvent) -> bool + Send + Sync>);
+#[derive(Debug)]
+struct EventSender<F: EventFilter> {
+    id: SubscriptionId,
+    sink: Sink<F::Event>,
+    filter: Arc<F>,
+}
 
-impl Callback {
-    fn new<F>(callback: F) -> Self
-    where
-        F: Fn(&ButtonEvent) -> bool + Send + Sync + 'static,
-    {
-        Self(Arc::new(callback))
-    }
+pub(super) type TransformFn<E> = Arc<dyn Fn(E) -> Vec<E> + Send + Sync>;
+
+struct Transformer<F: EventFilter> {
+    id: SubscriptionId,
+    filter: Arc<F>,
+    transform: TransformFn<F::Event>,
 }
 
-impl Debug for Callback {
+impl<F: EventFilter> Debug for Transformer<F> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str("Callback")
+        f.debug_struct("Transformer")
+            .field("id", &self.id)
+            .field("filter", &self.filter)
+            .finish()
     }
 }
 
-/// Filters input events.
-///
-/// # Examples
-///
-/// ```
-/// use hookmap::prelude::*;
-///
-/// let filter = Filter::new()
-///     .target(Button::A)
-///     .action(ButtonAction::Press);
-/// ```
-///
-#[derive(Debug, Default, Clone)]
-pub struct Filter {
-    target: Option<Target>,
-    action: Option<ButtonAction>,
-    callback: Vec<Callback>,
+#[derive(Debug)]
+pub struct EventBroker<F: EventFilter> {
+    dispatch: Vec<EventSender<F>>,
+    block: Vec<EventSender<F>>,
+    transform: Vec<Transformer<F>>,
+    next_id: u64,
 }
 
-impl Filter {
-    /// Creates a new instance of [`Filter`]
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use hookmap::interceptor::Filter;
-    ///
-    /// let filter = Filter::new();
-    /// ```
-    ///
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Set the target of events.
-    /// This setting will be overridden by self.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use hookmap::prelude::*;
-    ///
-    /// let filter = Filter::new().target(buttons!(A, B));
-    /// ```
-    ///
-    pub fn target(mut self, target: impl Into<ButtonArg>) -> Self {
-        self.target = Some(Target::from(target.into()));
-        self
+impl<F: EventFilter> Default for EventBroker<F> {
+    fn default() -> Self {
+        Self {
+            dispatch: Vec::new(),
+            block: Vec::new(),
+            transform: Vec::new(),
+            next_id: 0,
+        }
     }
+}
 
-    /// Set the action of events.
-    /// This setting will be overridden by self.
-    ///
-    /// # Examples
-    /// ```
-    /// use hookmap::prelude::*;
-    ///
-    /// let filter = Filter::new().action(ButtonAction::Press);
-    /// ```
-    ///
-    pub fn action(mut self, action: ButtonAction) -> Self {
-        self.action = Some(action);
-        self
+impl<F: EventFilter> EventBroker<F> {
+    fn next_id(&mut self) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        id
     }
 
-    pub fn callback<F>(mut self, callback: F) -> Self
-    where
-        F: Fn(&ButtonEvent) -> bool + Send + Sync + 'static,
-    {
-        self.callback.push(Callback::new(callback));
-        self
-    }
+    fn add(
+        &mut self,
+        filter: Arc<F>,
+        operation: NativeEventOperation,
+        sink: Sink<F::Event>,
+    ) -> SubscriptionId {
+        let id = self.next_id();
+        let event_sender = EventSender { id, sink, filter };
+        match operation {
+            NativeEventOperation::Block => self.block.push(event_sender),
+            NativeEventOperation::Dispatch => self.dispatch.push(event_sender),
+        }
 
-    fn filter(&self, event: &ButtonEvent) -> bool {
-        self.target
-            .as_ref()
-            .map_or(true, |target| target.filter(event.target))
-            && self.action.map_or(true, |action| action == event.action)
-            && self.callback.iter().all(|callback| callback.0(event))
+        id
     }
-}
 
-#[derive(Debug)]
-struct EventSender {
-    tx: SyncSender<ButtonEvent>,
-    filter: Arc<Filter>,
-}
-
-#[derive(Debug, Default)]
-pub(super) struct EventBroker {
-    dispatch: Vec<EventSender>,
-    block: Vec<EventSender>,
-}
-
-impl EventBroker {
     pub(super) fn subscribe_once(
         &mut self,
-        filter: Arc<Filter>,
+        filter: Arc<F>,
         operation: NativeEventOperation,
-    ) -> Receiver<ButtonEvent> {
+    ) -> (SubscriptionId, Receiver<F::Event>) {
         let (tx, rx) = mpsc::sync_channel(1);
-        let event_sender = EventSender { tx, filter };
+        let id = self.add(filter, operation, Sink::Once(tx));
+        (id, rx)
+    }
 
-        match operation {
-            NativeEventOperation::Block => self.block.push(event_sender),
-            NativeEventOperation::Dispatch => self.dispatch.push(event_sender),
-        }
+    /// Subscribes until the subscription is removed by [`EventBroker::unsubscribe`]
+    /// or the queue is closed.
+    pub(super) fn subscribe(
+        &mut self,
+        filter: Arc<F>,
+        operation: NativeEventOperation,
+        queue: Arc<Queue<F::Event>>,
+    ) -> SubscriptionId {
+        self.add(filter, operation, Sink::Persistent(queue))
+    }
 
-        rx
+    pub(super) fn subscribe_transform(
+        &mut self,
+        filter: Arc<F>,
+        transform: TransformFn<F::Event>,
+    ) -> SubscriptionId {
+        let id = self.next_id();
+        self.transform.push(Transformer {
+            id,
+            filter,
+            transform,
+        });
+        id
     }
 
-    pub(super) fn publish(&mut self, event: ButtonEvent) -> NativeEventOperation {
-        if !self.block.is_empty() {
-            let satisfied_index = self
-                .block
-                .iter()
-                .rposition(|EventSender { filter, .. }| filter.filter(&event));
-            if let Some(index) = satisfied_index {
-                let EventSender { tx, .. } = self.block.remove(index);
-                tx.send(event).unwrap();
+    /// Removes the subscription if it has not received an event yet.
+    pub(super) fn unsubscribe(&mut self, id: SubscriptionId) {
+        self.block.retain(|sender| sender.id != id);
+        self.dispatch.retain(|sender| sender.id != id);
+        self.transform.retain(|transformer| transformer.id != id);
+    }
+
+    /// Returns the most recently subscribed transformation whose filter matches the event.
+    pub(super) fn find_transform(&self, event: &F::Event) -> Option<TransformFn<F::Event>> {
+        self.transform
+            .iter()
+            .rev()
+            .find(|transformer| transformer.filter.filter(event))
+            .map(|transformer| Arc::clone(&transformer.transform))
+    }
+
+    pub(super) fn publish(&mut self, event: F::Event) -> NativeEventOperation {
+        let mut end = self.block.len();
+        while let Some(index) = self.block[..end]
+            .iter()
+            .rposition(|EventSender { filter, .. }| filter.filter(&event))
+        {
+            let is_taken = self.block[index].sink.send(event);
+            if !self.block[index].sink.is_reusable() {
+                self.block.remove(index);
+            }
+            if is_taken {
                 return NativeEventOperation::Block;
             }
+            end = index;
         }
 
         // drain_filter (https://doc.rust-lang.org/std/vec/struct.Vec.html#method.drain_filter)
         let mut i = 0;
         while i < self.dispatch.len() {
+            let sink = &self.dispatch[i].sink;
             if self.dispatch[i].filter.filter(&event) {
-                self.dispatch.remove(i).tx.send(event).unwrap();
-            } else {
-                i += 1;
+                sink.send(event);
+                if !sink.is_reusable() {
+                    self.dispatch.remove(i);
+                    continue;
+                }
             }
+            i += 1;
         }
 
         NativeEventOperation::Dispatch
@@ -196,14 +191,17 @@ impl EventBroker {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::buttons;
+    use crate::runtime::filter::Filter;
+    use crate::runtime::queue::OverflowPolicy;
     use hookmap_core::button::{Button, ButtonAction};
+    use hookmap_core::event::ButtonEvent;
 
     fn create_button_event(target: Button, action: ButtonAction) -> ButtonEvent {
         ButtonEvent {
             target,
             action,
             injected: false,
+            is_repeat: false,
         }
     }
 
@@ -211,7 +209,7 @@ mod tests {
     fn event_sender_sends_block_events() {
         let mut broker = EventBroker::default();
         let filter = Arc::new(Filter::new());
-        let rx = broker.subscribe_once(filter, NativeEventOperation::Block);
+        let (_, rx) = broker.subscribe_once(filter, NativeEventOperation::Block);
 
         let event = create_button_event(Button::A, ButtonAction::Press);
         broker.publish(event);
@@ -222,7 +220,7 @@ mod tests {
     fn event_sender_does_not_send_block_events() {
         let mut broker = EventBroker::default();
         let filter = Filter::new().target(Button::A);
-        let rx = broker.subscribe_once(Arc::new(filter), NativeEventOperation::Block);
+        let (_, rx) = broker.subscribe_once(Arc::new(filter), NativeEventOperation::Block);
 
         let event = create_button_event(Button::B, ButtonAction::Press);
         broker.publish(event);
@@ -234,9 +232,9 @@ mod tests {
         let mut broker = EventBroker::default();
         let filter = Arc::new(Filter::new());
 
-        let rx_dispatch =
+        let (_, rx_dispatch) =
             broker.subscribe_once(Arc::clone(&filter), NativeEventOperation::Dispatch);
-        let rx_block = broker.subscribe_once(filter, NativeEventOperation::Block);
+        let (_, rx_block) = broker.subscribe_once(filter, NativeEventOperation::Block);
 
         let event = create_button_event(Button::A, ButtonAction::Press);
         broker.publish(event);
@@ -255,8 +253,8 @@ mod tests {
         let mut broker = EventBroker::default();
         let filter = Arc::new(Filter::new());
 
-        let rx1 = broker.subscribe_once(Arc::clone(&filter), NativeEventOperation::Dispatch);
-        let rx2 = broker.subscribe_once(filter, NativeEventOperation::Dispatch);
+        let (_, rx1) = broker.subscribe_once(Arc::clone(&filter), NativeEventOperation::Dispatch);
+        let (_, rx2) = broker.subscribe_once(filter, NativeEventOperation::Dispatch);
 
         let event = create_button_event(Button::C, ButtonAction::Release);
         broker.publish(event);
@@ -265,62 +263,94 @@ mod tests {
         assert_eq!(rx2.recv().unwrap(), event);
     }
 
-    fn test_filter(expect: bool, filter: &Filter, target: Button, action: ButtonAction) {
-        let event = create_button_event(target, action);
-        assert_eq!(expect, filter.filter(&event));
-    }
-
     #[test]
-    fn filtering_events_by_ignored_target() {
-        let filter = Filter::new().target(buttons!(A, !B));
-        test_filter(true, &filter, Button::A, ButtonAction::Press);
-        test_filter(true, &filter, Button::A, ButtonAction::Release);
-        test_filter(false, &filter, Button::B, ButtonAction::Press);
-        test_filter(false, &filter, Button::B, ButtonAction::Release);
-    }
+    fn event_sender_skips_dropped_block_receiver() {
+        let mut broker = EventBroker::default();
+        let filter = Arc::new(Filter::new());
 
-    #[test]
-    fn filtering_events_by_target_matching_conditions() {
-        let filter = Filter::new().target(buttons!(A, B));
-        test_filter(true, &filter, Button::A, ButtonAction::Press);
-        test_filter(true, &filter, Button::A, ButtonAction::Release);
-        test_filter(true, &filter, Button::B, ButtonAction::Press);
-        test_filter(true, &filter, Button::B, ButtonAction::Release);
+        let (_, rx) = broker.subscribe_once(Arc::clone(&filter), NativeEventOperation::Block);
+        drop(broker.subscribe_once(filter, NativeEventOperation::Block).1);
+
+        let event = create_button_event(Button::A, ButtonAction::Press);
+        assert_eq!(broker.publish(event), NativeEventOperation::Block);
+        assert_eq!(rx.recv().unwrap(), event);
+
+        assert_eq!(broker.publish(event), NativeEventOperation::Dispatch);
     }
 
     #[test]
-    fn filtering_events_by_target_not_matching_conditions() {
-        let filter = Filter::new().target(buttons!(A, B));
-        test_filter(false, &filter, Button::C, ButtonAction::Press);
-        test_filter(false, &filter, Button::C, ButtonAction::Release);
+    fn unsubscribed_sender_does_not_receive_events() {
+        let mut broker = EventBroker::default();
+        let filter = Arc::new(Filter::new());
+
+        let (id, rx_block) =
+            broker.subscribe_once(Arc::clone(&filter), NativeEventOperation::Block);
+        let (_, rx_dispatch) = broker.subscribe_once(filter, NativeEventOperation::Dispatch);
+        broker.unsubscribe(id);
+
+        let event = create_button_event(Button::A, ButtonAction::Press);
+        assert_eq!(broker.publish(event), NativeEventOperation::Dispatch);
+        assert!(rx_block.try_recv().is_err());
+        assert_eq!(rx_dispatch.recv().unwrap(), event);
     }
 
     #[test]
-    fn filtering_events_by_action() {
-        let filter = Filter::new().action(ButtonAction::Press);
-        test_filter(true, &filter, Button::A, ButtonAction::Press);
-        test_filter(false, &filter, Button::A, ButtonAction::Release);
-
-        let filter = Filter::new().action(ButtonAction::Release);
-        test_filter(false, &filter, Button::A, ButtonAction::Press);
-        test_filter(true, &filter, Button::A, ButtonAction::Release);
+    fn persistent_subscriber_receives_all_events() {
+        let mut broker = EventBroker::default();
+        let queue = Arc::new(Queue::new(2, OverflowPolicy::Dispatch));
+        broker.subscribe(
+            Arc::new(Filter::new()),
+            NativeEventOperation::Block,
+            Arc::clone(&queue),
+        );
+
+        let event1 = create_button_event(Button::A, ButtonAction::Press);
+        let event2 = create_button_event(Button::A, ButtonAction::Release);
+        assert_eq!(broker.publish(event1), NativeEventOperation::Block);
+        assert_eq!(broker.publish(event2), NativeEventOperation::Block);
+        assert_eq!(broker.publish(event1), NativeEventOperation::Dispatch);
+
+        assert_eq!(queue.try_pop(), Some(event1));
+        assert_eq!(queue.try_pop(), Some(event2));
+        assert_eq!(queue.try_pop(), None);
     }
 
     #[test]
-    fn filtering_events_by_target_and_action() {
-        let filter = Filter::new()
-            .target(buttons!(A, C))
-            .action(ButtonAction::Press);
-        test_filter(true, &filter, Button::A, ButtonAction::Press);
-        test_filter(false, &filter, Button::A, ButtonAction::Release);
-        test_filter(false, &filter, Button::B, ButtonAction::Press);
-        test_filter(false, &filter, Button::B, ButtonAction::Release);
+    fn closed_persistent_subscriber_is_removed() {
+        let mut broker = EventBroker::default();
+        let queue = Arc::new(Queue::new(1, OverflowPolicy::DropOldest));
+        broker.subscribe(
+            Arc::new(Filter::new()),
+            NativeEventOperation::Block,
+            Arc::clone(&queue),
+        );
+        queue.close();
+
+        let event = create_button_event(Button::A, ButtonAction::Press);
+        assert_eq!(broker.publish(event), NativeEventOperation::Dispatch);
+        assert!(broker.block.is_empty());
     }
 
     #[test]
-    fn filtering_events_by_callback() {
-        let filter = Filter::new().callback(|e| e.action == ButtonAction::Press);
-        test_filter(true, &filter, Button::A, ButtonAction::Press);
-        test_filter(false, &filter, Button::A, ButtonAction::Release);
+    fn latest_matching_transform_is_found() {
+        let mut broker = EventBroker::default();
+        broker.subscribe_transform(Arc::new(Filter::new()), Arc::new(|_| Vec::new()));
+        let id = broker.subscribe_transform(
+            Arc::new(Filter::new().target(Button::A)),
+            Arc::new(|e| vec![e, e]),
+        );
+
+        let event = create_button_event(Button::A, ButtonAction::Press);
+        assert_eq!(
+            broker.find_transform(&event).unwrap()(event),
+            vec![event, event]
+        );
+
+        let event = create_button_event(Button::B, ButtonAction::Press);
+        assert!(broker.find_transform(&event).unwrap()(event).is_empty());
+
+        broker.unsubscribe(id);
+        let event = create_button_event(Button::A, ButtonAction::Press);
+        assert!(broker.find_transform(&event).unwrap()(event).is_empty());
     }
 }
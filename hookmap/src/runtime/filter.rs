@@ -0,0 +1,929 @@
+use hookmap_core::button::{Button, ButtonAction, ButtonKind};
+use hookmap_core::event::{ButtonEvent, CursorEvent, WheelAxis, WheelEvent, WHEEL_DELTA};
+use hookmap_core::mouse;
+
+use super::button_state::RealButtonState;
+use super::error::{self, CallbackId, CallbackKind};
+use super::event_broker::EventBroker;
+use crate::hook::ButtonState;
+use crate::hotkey::Modifiers;
+use crate::macros::button_arg::ButtonArg;
+
+use std::sync::{Arc, Mutex};
+use std::{collections::HashSet, fmt::Debug};
+
+use once_cell::sync::Lazy;
+
+/// Filters events of a specific type.
+///
+/// This is implemented by [`Filter`], [`WheelFilter`] and [`CursorFilter`],
+/// and determines which events an [`Interceptor`](super::interceptor::Interceptor) captures.
+pub trait EventFilter: Debug + Send + Sync + Sized + 'static {
+    /// Type of events to be filtered.
+    type Event: Debug + Copy + Send + 'static;
+
+    /// Returns `true` if `event` satisfies this filter.
+    fn filter(&self, event: &Self::Event) -> bool;
+
+    #[doc(hidden)]
+    fn broker() -> &'static Mutex<EventBroker<Self>>;
+
+    /// Generates the event. This is used to inject events replaced by
+    /// [`Interceptor::transform`](super::interceptor::Interceptor::transform).
+    #[doc(hidden)]
+    fn inject(event: Self::Event);
+}
+
+#[derive(Debug, Clone)]
+struct Target {
+    target: Option<HashSet<Button>>,
+    ignored: Option<HashSet<Button>>,
+}
+
+impl Target {
+    #[allow(clippy::needless_return)]
+    fn filter(&self, button: Button) -> bool {
+        return match &self.target {
+            Some(set) => set.contains(&button),
+            None => true,
+        } && match &self.ignored {
+            Some(set) => !set.contains(&button),
+            None => true,
+        };
+    }
+}
+
+impl From<ButtonArg> for Target {
+    fn from(args: ButtonArg) -> Self {
+        let mut target = args.iter_plain().peekable();
+        let mut ignored = args.iter_not().peekable();
+
+        Target {
+            target: target.peek().copied().map(|_| target.collect()),
+            ignored: ignored.peek().copied().map(|_| ignored.collect()),
+        }
+    }
+}
+
+struct Callback<E>(Arc<dyn Fn(&E) -> bool + Send + Sync>);
+
+impl<E> Callback<E> {
+    fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(callback))
+    }
+
+    fn call(&self, event: &E) -> bool {
+        let id = CallbackId::new(CallbackKind::Filter, []);
+        error::catch(&id, || self.0(event)).unwrap_or(false)
+    }
+}
+
+impl<E> Clone for Callback<E> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<E> Debug for Callback<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Callback")
+    }
+}
+
+/// Filters input events.
+///
+/// Conditions set by the methods are combined with AND,
+/// and filters can be combined with [`Filter::or`] and [`Filter::not`].
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::prelude::*;
+///
+/// let filter = Filter::new()
+///     .target(Button::A)
+///     .action(ButtonAction::Press);
+/// ```
+///
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+    target: Option<Target>,
+    action: Option<ButtonAction>,
+    injected: Option<bool>,
+    modifiers: Option<Modifiers>,
+    kind: Option<ButtonKind>,
+    callback: Vec<Callback<ButtonEvent>>,
+    composite: Option<Composite>,
+}
+
+#[derive(Debug, Clone)]
+enum Composite {
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    /// Creates a new instance of [`Filter`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::interceptor::Filter;
+    ///
+    /// let filter = Filter::new();
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the target of events.
+    /// This setting will be overridden by self.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// let filter = Filter::new().target(buttons!(A, B));
+    /// ```
+    ///
+    pub fn target(mut self, target: impl Into<ButtonArg>) -> Self {
+        self.target = Some(Target::from(target.into()));
+        self
+    }
+
+    /// Set the action of events.
+    /// This setting will be overridden by self.
+    ///
+    /// # Examples
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// let filter = Filter::new().action(ButtonAction::Press);
+    /// ```
+    ///
+    pub fn action(mut self, action: ButtonAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// Set whether events are generated by this program.
+    /// This setting will be overridden by self.
+    ///
+    /// # Examples
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// // Ignores events sent by hotkeys.
+    /// let filter = Filter::new().injected(false);
+    /// ```
+    ///
+    pub fn injected(mut self, injected: bool) -> Self {
+        self.injected = Some(injected);
+        self
+    }
+
+    /// Set the modifier keys that must be pressed (or released with `!`) when an event occurs.
+    /// This setting will be overridden by self.
+    ///
+    /// # Examples
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// // Captures `A` while `Shift` is pressed and `Ctrl` is released.
+    /// let filter = Filter::new()
+    ///     .target(Button::A)
+    ///     .modifiers(buttons!(Shift, !Ctrl));
+    /// ```
+    ///
+    pub fn modifiers(mut self, modifiers: impl Into<ButtonArg>) -> Self {
+        self.modifiers = Some(Modifiers::from(modifiers.into()));
+        self
+    }
+
+    /// Set the kind of the target of events.
+    /// This setting will be overridden by self.
+    ///
+    /// # Examples
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// let filter = Filter::new().kind(ButtonKind::Mouse);
+    /// ```
+    ///
+    pub fn kind(mut self, kind: ButtonKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Adds a condition that the event must satisfy.
+    /// Prefer the other methods if possible, since a callback cannot be inspected.
+    ///
+    /// # Examples
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// let filter = Filter::new().callback(|e| e.target != Button::A);
+    /// ```
+    ///
+    pub fn callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&ButtonEvent) -> bool + Send + Sync + 'static,
+    {
+        self.callback.push(Callback::new(callback));
+        self
+    }
+
+    /// Creates a filter that is satisfied by events satisfying either `self` or `other`.
+    ///
+    /// # Examples
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// // Presses of `A` and releases of `B`.
+    /// let filter = Filter::new()
+    ///     .target(Button::A)
+    ///     .action(ButtonAction::Press)
+    ///     .or(Filter::new().target(Button::B).action(ButtonAction::Release));
+    /// ```
+    ///
+    pub fn or(self, other: Filter) -> Self {
+        Self {
+            composite: Some(Composite::Or(Box::new(self), Box::new(other))),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a filter that is satisfied by events not satisfying `self`.
+    ///
+    /// # Examples
+    /// ```
+    /// use hookmap::prelude::*;
+    ///
+    /// // All events except for presses of `A`.
+    /// let filter = Filter::new()
+    ///     .target(Button::A)
+    ///     .action(ButtonAction::Press)
+    ///     .not();
+    /// ```
+    ///
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Self {
+            composite: Some(Composite::Not(Box::new(self))),
+            ..Self::default()
+        }
+    }
+
+    fn matches(&self, event: &ButtonEvent, state: &impl ButtonState) -> bool {
+        self.target
+            .as_ref()
+            .map_or(true, |target| target.filter(event.target))
+            && self.action.map_or(true, |action| action == event.action)
+            && self
+                .injected
+                .into_iter()
+                .all(|injected| injected == event.injected)
+            && self
+                .kind
+                .into_iter()
+                .all(|kind| kind == event.target.kind())
+            && self
+                .modifiers
+                .iter()
+                .all(|modifiers| modifiers.is_matched(state))
+            && self.composite.iter().all(|composite| match composite {
+                Composite::Or(left, right) => {
+                    left.matches(event, state) || right.matches(event, state)
+                }
+                Composite::Not(filter) => !filter.matches(event, state),
+            })
+            && self.callback.iter().all(|callback| callback.call(event))
+    }
+}
+
+impl EventFilter for Filter {
+    type Event = ButtonEvent;
+
+    fn filter(&self, event: &ButtonEvent) -> bool {
+        // The button state has already been updated by the event when this is called.
+        self.matches(event, &RealButtonState)
+    }
+
+    fn broker() -> &'static Mutex<EventBroker<Self>> {
+        static BROKER: Lazy<Mutex<EventBroker<Filter>>> = Lazy::new(Mutex::default);
+        &BROKER
+    }
+
+    fn inject(event: ButtonEvent) {
+        match event.action {
+            ButtonAction::Press => event.target.press(),
+            ButtonAction::Release => event.target.release(),
+        }
+    }
+}
+
+/// Direction of mouse wheel rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WheelDirection {
+    /// Upward rotation. [`WheelEvent::delta`] is positive.
+    Up,
+
+    /// Downward rotation. [`WheelEvent::delta`] is negative.
+    Down,
+
+    /// Rightward scrolling. [`WheelEvent::delta`] is positive.
+    Right,
+
+    /// Leftward scrolling. [`WheelEvent::delta`] is negative.
+    Left,
+}
+
+/// Filters mouse wheel events.
+///
+/// Only vertical rotation is matched by default, as before horizontal scrolling was reported.
+/// Use [`WheelFilter::axis`], [`WheelFilter::any_axis`] or [`WheelFilter::direction`]
+/// to match horizontal scrolling.
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::interceptor::{WheelDirection, WheelFilter};
+///
+/// let filter = WheelFilter::new()
+///     .direction(WheelDirection::Up)
+///     .min_magnitude(2);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct WheelFilter {
+    axis: Option<WheelAxis>,
+    direction: Option<WheelDirection>,
+    min_magnitude: Option<u32>,
+    max_magnitude: Option<u32>,
+    callback: Vec<Callback<WheelEvent>>,
+}
+
+impl Default for WheelFilter {
+    fn default() -> Self {
+        Self {
+            axis: Some(WheelAxis::Vertical),
+            direction: None,
+            min_magnitude: None,
+            max_magnitude: None,
+            callback: Vec::new(),
+        }
+    }
+}
+
+impl WheelFilter {
+    /// Creates a new instance of [`WheelFilter`] that matches vertical rotation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::interceptor::WheelFilter;
+    ///
+    /// let filter = WheelFilter::new();
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the direction of rotation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::interceptor::{WheelDirection, WheelFilter};
+    ///
+    /// let filter = WheelFilter::new().direction(WheelDirection::Down);
+    /// ```
+    ///
+    pub fn direction(mut self, direction: WheelDirection) -> Self {
+        self.direction = Some(direction);
+        self.axis = Some(match direction {
+            WheelDirection::Up | WheelDirection::Down => WheelAxis::Vertical,
+            WheelDirection::Right | WheelDirection::Left => WheelAxis::Horizontal,
+        });
+        self
+    }
+
+    /// Set the axis of rotation. The default is [`WheelAxis::Vertical`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::device::WheelAxis;
+    /// use hookmap::interceptor::WheelFilter;
+    ///
+    /// let filter = WheelFilter::new().axis(WheelAxis::Horizontal);
+    /// ```
+    ///
+    pub fn axis(mut self, axis: WheelAxis) -> Self {
+        self.axis = Some(axis);
+        self
+    }
+
+    /// Matches rotation along both axes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::interceptor::WheelFilter;
+    ///
+    /// let filter = WheelFilter::new().any_axis();
+    /// ```
+    ///
+    pub fn any_axis(mut self) -> Self {
+        self.axis = None;
+        self
+    }
+
+    /// Set the minimum amount of rotation in notches.
+    ///
+    /// This is compared with [`WheelEvent::hi_res_delta`] in units of 1/[`WHEEL_DELTA`] notch,
+    /// so a rotation smaller than a notch does not reach a magnitude of `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::interceptor::WheelFilter;
+    ///
+    /// let filter = WheelFilter::new().min_magnitude(2);
+    /// ```
+    ///
+    pub fn min_magnitude(mut self, magnitude: u32) -> Self {
+        self.min_magnitude = Some(magnitude);
+        self
+    }
+
+    /// Set the maximum amount of rotation in notches.
+    ///
+    /// This is compared with [`WheelEvent::hi_res_delta`] in units of 1/[`WHEEL_DELTA`] notch,
+    /// so a rotation of one and a half notches exceeds a magnitude of `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::interceptor::WheelFilter;
+    ///
+    /// let filter = WheelFilter::new().max_magnitude(1);
+    /// ```
+    ///
+    pub fn max_magnitude(mut self, magnitude: u32) -> Self {
+        self.max_magnitude = Some(magnitude);
+        self
+    }
+
+    /// Adds a condition that the event must satisfy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::device::WHEEL_DELTA;
+    /// use hookmap::interceptor::WheelFilter;
+    ///
+    /// let filter = WheelFilter::new().callback(|e| e.hi_res_delta % WHEEL_DELTA == 0);
+    /// ```
+    ///
+    pub fn callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&WheelEvent) -> bool + Send + Sync + 'static,
+    {
+        self.callback.push(Callback::new(callback));
+        self
+    }
+}
+
+impl EventFilter for WheelFilter {
+    type Event = WheelEvent;
+
+    fn filter(&self, event: &WheelEvent) -> bool {
+        let magnitude = u64::from(event.hi_res_delta.unsigned_abs());
+        let notches = |magnitude: u32| u64::from(magnitude) * u64::from(WHEEL_DELTA.unsigned_abs());
+        self.axis.into_iter().all(|axis| axis == event.axis)
+            && self.direction.into_iter().all(|direction| match direction {
+                WheelDirection::Up => event.axis == WheelAxis::Vertical && event.hi_res_delta > 0,
+                WheelDirection::Down => event.axis == WheelAxis::Vertical && event.hi_res_delta < 0,
+                WheelDirection::Right => {
+                    event.axis == WheelAxis::Horizontal && event.hi_res_delta > 0
+                }
+                WheelDirection::Left => {
+                    event.axis == WheelAxis::Horizontal && event.hi_res_delta < 0
+                }
+            })
+            && self
+                .min_magnitude
+                .into_iter()
+                .all(|min| notches(min) <= magnitude)
+            && self
+                .max_magnitude
+                .into_iter()
+                .all(|max| magnitude <= notches(max))
+            && self.callback.iter().all(|callback| callback.call(event))
+    }
+
+    fn broker() -> &'static Mutex<EventBroker<Self>> {
+        static BROKER: Lazy<Mutex<EventBroker<WheelFilter>>> = Lazy::new(Mutex::default);
+        &BROKER
+    }
+
+    fn inject(event: WheelEvent) {
+        mouse::rotate_hi_res(event.axis, event.hi_res_delta);
+    }
+}
+
+/// Direction of mouse cursor movement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorDirection {
+    /// Leftward movement. The x component of [`CursorEvent::delta`] is negative.
+    Left,
+
+    /// Rightward movement. The x component of [`CursorEvent::delta`] is positive.
+    Right,
+
+    /// Upward movement. The y component of [`CursorEvent::delta`] is negative.
+    Up,
+
+    /// Downward movement. The y component of [`CursorEvent::delta`] is positive.
+    Down,
+}
+
+/// Filters mouse cursor events.
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::interceptor::{CursorDirection, CursorFilter};
+///
+/// let filter = CursorFilter::new()
+///     .direction(CursorDirection::Right)
+///     .min_magnitude(5.0);
+/// ```
+///
+#[derive(Debug, Default, Clone)]
+pub struct CursorFilter {
+    direction: Vec<CursorDirection>,
+    min_magnitude: Option<f64>,
+    max_magnitude: Option<f64>,
+    callback: Vec<Callback<CursorEvent>>,
+}
+
+impl CursorFilter {
+    /// Creates a new instance of [`CursorFilter`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::interceptor::CursorFilter;
+    ///
+    /// let filter = CursorFilter::new();
+    /// ```
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the direction of movement.
+    /// Movements that have a component in all the added directions satisfy this filter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::interceptor::{CursorDirection, CursorFilter};
+    ///
+    /// // Moves to the upper left.
+    /// let filter = CursorFilter::new()
+    ///     .direction(CursorDirection::Left)
+    ///     .direction(CursorDirection::Up);
+    /// ```
+    ///
+    pub fn direction(mut self, direction: CursorDirection) -> Self {
+        self.direction.push(direction);
+        self
+    }
+
+    /// Set the minimum distance of movement.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::interceptor::CursorFilter;
+    ///
+    /// let filter = CursorFilter::new().min_magnitude(10.0);
+    /// ```
+    ///
+    pub fn min_magnitude(mut self, magnitude: f64) -> Self {
+        self.min_magnitude = Some(magnitude);
+        self
+    }
+
+    /// Set the maximum distance of movement.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::interceptor::CursorFilter;
+    ///
+    /// let filter = CursorFilter::new().max_magnitude(10.0);
+    /// ```
+    ///
+    pub fn max_magnitude(mut self, magnitude: f64) -> Self {
+        self.max_magnitude = Some(magnitude);
+        self
+    }
+
+    /// Adds a condition that the event must satisfy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hookmap::interceptor::CursorFilter;
+    ///
+    /// let filter = CursorFilter::new().callback(|e| !e.injected);
+    /// ```
+    ///
+    pub fn callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&CursorEvent) -> bool + Send + Sync + 'static,
+    {
+        self.callback.push(Callback::new(callback));
+        self
+    }
+}
+
+impl EventFilter for CursorFilter {
+    type Event = CursorEvent;
+
+    fn filter(&self, event: &CursorEvent) -> bool {
+        let (dx, dy) = event.delta;
+        let magnitude = f64::from(dx).hypot(f64::from(dy));
+        self.direction.iter().all(|direction| match direction {
+            CursorDirection::Left => dx < 0,
+            CursorDirection::Right => dx > 0,
+            CursorDirection::Up => dy < 0,
+            CursorDirection::Down => dy > 0,
+        }) && self.min_magnitude.into_iter().all(|min| min <= magnitude)
+            && self.max_magnitude.into_iter().all(|max| magnitude <= max)
+            && self.callback.iter().all(|callback| callback.call(event))
+    }
+
+    fn broker() -> &'static Mutex<EventBroker<Self>> {
+        static BROKER: Lazy<Mutex<EventBroker<CursorFilter>>> = Lazy::new(Mutex::default);
+        &BROKER
+    }
+
+    fn inject(event: CursorEvent) {
+        let (dx, dy) = event.delta;
+        mouse::move_relative(dx, dy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buttons;
+    use hookmap_core::event::MonitorId;
+
+    fn create_button_event(target: Button, action: ButtonAction) -> ButtonEvent {
+        ButtonEvent {
+            target,
+            action,
+            injected: false,
+            is_repeat: false,
+        }
+    }
+
+    fn test_filter(expect: bool, filter: &Filter, target: Button, action: ButtonAction) {
+        let event = create_button_event(target, action);
+        assert_eq!(expect, filter.filter(&event));
+    }
+
+    #[test]
+    fn filtering_events_by_ignored_target() {
+        let filter = Filter::new().target(buttons!(A, !B));
+        test_filter(true, &filter, Button::A, ButtonAction::Press);
+        test_filter(true, &filter, Button::A, ButtonAction::Release);
+        test_filter(false, &filter, Button::B, ButtonAction::Press);
+        test_filter(false, &filter, Button::B, ButtonAction::Release);
+    }
+
+    #[test]
+    fn filtering_events_by_target_matching_conditions() {
+        let filter = Filter::new().target(buttons!(A, B));
+        test_filter(true, &filter, Button::A, ButtonAction::Press);
+        test_filter(true, &filter, Button::A, ButtonAction::Release);
+        test_filter(true, &filter, Button::B, ButtonAction::Press);
+        test_filter(true, &filter, Button::B, ButtonAction::Release);
+    }
+
+    #[test]
+    fn filtering_events_by_target_not_matching_conditions() {
+        let filter = Filter::new().target(buttons!(A, B));
+        test_filter(false, &filter, Button::C, ButtonAction::Press);
+        test_filter(false, &filter, Button::C, ButtonAction::Release);
+    }
+
+    #[test]
+    fn filtering_events_by_action() {
+        let filter = Filter::new().action(ButtonAction::Press);
+        test_filter(true, &filter, Button::A, ButtonAction::Press);
+        test_filter(false, &filter, Button::A, ButtonAction::Release);
+
+        let filter = Filter::new().action(ButtonAction::Release);
+        test_filter(false, &filter, Button::A, ButtonAction::Press);
+        test_filter(true, &filter, Button::A, ButtonAction::Release);
+    }
+
+    #[test]
+    fn filtering_events_by_target_and_action() {
+        let filter = Filter::new()
+            .target(buttons!(A, C))
+            .action(ButtonAction::Press);
+        test_filter(true, &filter, Button::A, ButtonAction::Press);
+        test_filter(false, &filter, Button::A, ButtonAction::Release);
+        test_filter(false, &filter, Button::B, ButtonAction::Press);
+        test_filter(false, &filter, Button::B, ButtonAction::Release);
+    }
+
+    #[test]
+    fn filtering_events_by_callback() {
+        let filter = Filter::new().callback(|e| e.action == ButtonAction::Press);
+        test_filter(true, &filter, Button::A, ButtonAction::Press);
+        test_filter(false, &filter, Button::A, ButtonAction::Release);
+    }
+
+    #[test]
+    fn panicked_callback_does_not_match() {
+        let filter = Filter::new().callback(|_| panic!("filter panicked"));
+        test_filter(false, &filter, Button::A, ButtonAction::Press);
+    }
+
+    struct PressedButtons(Vec<Button>);
+
+    impl ButtonState for PressedButtons {
+        fn is_pressed(&self, button: Button) -> bool {
+            self.0.contains(&button)
+        }
+
+        fn is_released(&self, button: Button) -> bool {
+            !self.is_pressed(button)
+        }
+    }
+
+    #[test]
+    fn filtering_events_by_injected() {
+        let filter = Filter::new().injected(true);
+        test_filter(false, &filter, Button::A, ButtonAction::Press);
+
+        let event = ButtonEvent {
+            injected: true,
+            ..create_button_event(Button::A, ButtonAction::Press)
+        };
+        assert!(filter.filter(&event));
+    }
+
+    #[test]
+    fn filtering_events_by_kind() {
+        let filter = Filter::new().kind(ButtonKind::Mouse);
+        test_filter(true, &filter, Button::LeftButton, ButtonAction::Press);
+        test_filter(false, &filter, Button::A, ButtonAction::Press);
+    }
+
+    #[test]
+    fn filtering_events_by_modifiers() {
+        let filter = Filter::new().modifiers(buttons!(Shift, !Ctrl));
+        let event = create_button_event(Button::A, ButtonAction::Press);
+
+        assert!(filter.matches(&event, &PressedButtons(vec![Button::Shift])));
+        assert!(!filter.matches(&event, &PressedButtons(vec![])));
+        assert!(!filter.matches(&event, &PressedButtons(vec![Button::Shift, Button::Ctrl])));
+    }
+
+    #[test]
+    fn filtering_events_by_or() {
+        let filter = Filter::new()
+            .target(Button::A)
+            .or(Filter::new().action(ButtonAction::Release));
+        test_filter(true, &filter, Button::A, ButtonAction::Press);
+        test_filter(true, &filter, Button::B, ButtonAction::Release);
+        test_filter(false, &filter, Button::B, ButtonAction::Press);
+    }
+
+    #[test]
+    fn filtering_events_by_not() {
+        let filter = Filter::new()
+            .target(Button::A)
+            .not()
+            .action(ButtonAction::Press);
+        test_filter(false, &filter, Button::A, ButtonAction::Press);
+        test_filter(true, &filter, Button::B, ButtonAction::Press);
+        test_filter(false, &filter, Button::B, ButtonAction::Release);
+    }
+
+    fn test_wheel_filter(expect: bool, filter: &WheelFilter, delta: i32) {
+        test_wheel_filter_with_axis(expect, filter, WheelAxis::Vertical, delta * 120);
+    }
+
+    fn test_wheel_filter_with_axis(
+        expect: bool,
+        filter: &WheelFilter,
+        axis: WheelAxis,
+        hi_res_delta: i32,
+    ) {
+        let event = WheelEvent {
+            delta: hi_res_delta / 120,
+            hi_res_delta,
+            axis,
+            injected: false,
+        };
+        assert_eq!(expect, filter.filter(&event));
+    }
+
+    #[test]
+    fn filtering_wheel_events_by_direction() {
+        let filter = WheelFilter::new().direction(WheelDirection::Up);
+        test_wheel_filter(true, &filter, 1);
+        test_wheel_filter(false, &filter, -1);
+        test_wheel_filter_with_axis(true, &filter, WheelAxis::Vertical, 30);
+        test_wheel_filter_with_axis(false, &filter, WheelAxis::Horizontal, 120);
+
+        let filter = WheelFilter::new().direction(WheelDirection::Left);
+        test_wheel_filter_with_axis(true, &filter, WheelAxis::Horizontal, -120);
+        test_wheel_filter_with_axis(false, &filter, WheelAxis::Horizontal, 120);
+        test_wheel_filter_with_axis(false, &filter, WheelAxis::Vertical, -120);
+    }
+
+    #[test]
+    fn filtering_wheel_events_by_axis() {
+        let filter = WheelFilter::new();
+        test_wheel_filter_with_axis(true, &filter, WheelAxis::Vertical, -120);
+        test_wheel_filter_with_axis(false, &filter, WheelAxis::Horizontal, 120);
+
+        let filter = WheelFilter::new().axis(WheelAxis::Horizontal);
+        test_wheel_filter_with_axis(false, &filter, WheelAxis::Vertical, -120);
+        test_wheel_filter_with_axis(true, &filter, WheelAxis::Horizontal, 120);
+
+        let filter = WheelFilter::new().any_axis();
+        test_wheel_filter_with_axis(true, &filter, WheelAxis::Vertical, -120);
+        test_wheel_filter_with_axis(true, &filter, WheelAxis::Horizontal, 120);
+    }
+
+    #[test]
+    fn filtering_wheel_events_by_magnitude() {
+        let filter = WheelFilter::new().min_magnitude(2).max_magnitude(3);
+        test_wheel_filter(false, &filter, 1);
+        test_wheel_filter(true, &filter, -2);
+        test_wheel_filter(true, &filter, 3);
+        test_wheel_filter(false, &filter, -4);
+    }
+
+    #[test]
+    fn filtering_wheel_events_by_hi_res_magnitude() {
+        let filter = WheelFilter::new().min_magnitude(1);
+        test_wheel_filter_with_axis(false, &filter, WheelAxis::Vertical, 30);
+        test_wheel_filter_with_axis(true, &filter, WheelAxis::Vertical, -120);
+
+        let filter = WheelFilter::new().max_magnitude(1);
+        test_wheel_filter_with_axis(true, &filter, WheelAxis::Vertical, -30);
+        test_wheel_filter_with_axis(true, &filter, WheelAxis::Vertical, 120);
+        test_wheel_filter_with_axis(false, &filter, WheelAxis::Vertical, 180);
+
+        let filter = WheelFilter::new().max_magnitude(0);
+        test_wheel_filter_with_axis(false, &filter, WheelAxis::Vertical, 30);
+    }
+
+    fn test_cursor_filter(expect: bool, filter: &CursorFilter, delta: (i32, i32)) {
+        let event = CursorEvent {
+            delta,
+            position: (0, 0),
+            monitor: MonitorId::default(),
+            injected: false,
+        };
+        assert_eq!(expect, filter.filter(&event));
+    }
+
+    #[test]
+    fn filtering_cursor_events_by_direction() {
+        let filter = CursorFilter::new()
+            .direction(CursorDirection::Right)
+            .direction(CursorDirection::Up);
+        test_cursor_filter(true, &filter, (1, -1));
+        test_cursor_filter(false, &filter, (1, 0));
+        test_cursor_filter(false, &filter, (-1, -1));
+    }
+
+    #[test]
+    fn filtering_cursor_events_by_magnitude() {
+        let filter = CursorFilter::new().min_magnitude(5.0).max_magnitude(10.0);
+        test_cursor_filter(false, &filter, (3, 0));
+        test_cursor_filter(true, &filter, (3, 4));
+        test_cursor_filter(false, &filter, (0, -11));
+    }
+}
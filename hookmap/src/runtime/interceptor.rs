@@ -1,20 +1,79 @@
-//! Gets keyboard events dynamically.
+//! Gets keyboard and mouse events dynamically.
+//!
+//! Button events are captured with [`Filter`], mouse wheel events with [`WheelFilter`]
+//! and mouse cursor events with [`CursorFilter`].
+//! Captured events can also be replaced with other events by [`Interceptor::transform`].
 
-pub use super::event_broker::Filter;
+pub use super::filter::{
+    CursorDirection, CursorFilter, EventFilter, Filter, WheelDirection, WheelFilter,
+};
+pub use super::queue::OverflowPolicy;
 
-use hookmap_core::event::{ButtonEvent, NativeEventOperation};
+use hookmap_core::event::NativeEventOperation;
 
-use super::event_broker::EventBroker;
+use super::error::{self, CallbackId, CallbackKind};
+use super::event_broker::{EventBroker, SubscriptionId};
+use super::queue::Queue;
 
-use std::sync::Arc;
-use std::sync::Mutex;
+use std::marker::PhantomData;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 
-use once_cell::sync::Lazy;
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
-static BROKER: Lazy<Mutex<EventBroker>> = Lazy::new(Mutex::default);
+// A panic while the broker is locked must not stop the runtime,
+// so the poisoning is ignored.
+fn broker<F: EventFilter>() -> MutexGuard<'static, EventBroker<F>> {
+    F::broker().lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The default capacity of the queue of [`Iter`].
+pub const DEFAULT_QUEUE_CAPACITY: usize = 64;
 
-pub(super) fn publish_event(event: ButtonEvent) -> NativeEventOperation {
-    BROKER.lock().unwrap().publish(event)
+pub(super) fn publish_event<F: EventFilter>(event: F::Event) -> NativeEventOperation {
+    broker::<F>().publish(event)
+}
+
+/// Returns the replacement events if `event` is captured by [`Interceptor::transform`].
+pub(super) fn transform_event<F: EventFilter>(event: F::Event) -> Option<Vec<F::Event>> {
+    // The broker must not be locked while the transformation is running,
+    // since it may use interceptors.
+    let transform = broker::<F>().find_transform(&event)?;
+    let id = CallbackId::new(CallbackKind::Transform, []);
+    error::catch(&id, || transform(event))
+}
+
+/// A subscription to the broker that is removed when dropped.
+#[derive(Debug)]
+struct Subscription<F: EventFilter> {
+    id: SubscriptionId,
+    rx: Receiver<F::Event>,
+}
+
+impl<F: EventFilter> Subscription<F> {
+    fn new(filter: Arc<F>, operation: NativeEventOperation) -> Self {
+        let (id, rx) = broker::<F>().subscribe_once(filter, operation);
+        Self { id, rx }
+    }
+
+    /// Removes the subscription.
+    /// Returns the event if it has been published before the removal.
+    fn cancel(self) -> Option<F::Event> {
+        broker::<F>().unsubscribe(self.id);
+        self.rx.try_recv().ok()
+    }
+}
+
+impl<F: EventFilter> Drop for Subscription<F> {
+    fn drop(&mut self) {
+        broker::<F>().unsubscribe(self.id);
+    }
 }
 
 /// Set the hook that receives input events;
@@ -29,12 +88,25 @@ pub(super) fn publish_event(event: ButtonEvent) -> NativeEventOperation {
 /// println!("{:?}, {:?}", event.target, event.action);
 /// ```
 ///
-pub struct Interceptor {
-    filter: Arc<Filter>,
+/// Mouse events are captured in the same way.
+///
+/// ```no_run
+/// use hookmap::prelude::*;
+///
+/// let filter = WheelFilter::new().direction(WheelDirection::Up);
+/// let event = Interceptor::blocking(filter).get();
+/// println!("{}", event.delta);
+/// ```
+///
+pub struct Interceptor<F: EventFilter = Filter> {
+    filter: Arc<F>,
     native_event_operation: NativeEventOperation,
+    pending: Mutex<Option<Subscription<F>>>,
+    queue_capacity: usize,
+    overflow_policy: OverflowPolicy,
 }
 
-impl Interceptor {
+impl<F: EventFilter> Interceptor<F> {
     /// Creates a new instance of [`Interceptor`].
     /// Captured events are blocked.
     ///
@@ -46,10 +118,49 @@ impl Interceptor {
     /// println!("This event is blocked: {:?}", event);
     /// ```
     ///
-    pub fn blocking(filter: Filter) -> Self {
+    pub fn blocking(filter: F) -> Self {
         Self {
             filter: Arc::new(filter),
             native_event_operation: NativeEventOperation::Block,
+            pending: Mutex::default(),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            overflow_policy: OverflowPolicy::default(),
+        }
+    }
+
+    /// Replaces captured events with the events returned by `transform`.
+    ///
+    /// The captured event is blocked, and the returned events are generated in its place
+    /// before the subsequent events are processed. If `transform` returns an empty [`Vec`],
+    /// the captured event is just blocked. The generated events are not captured by
+    /// interceptors and do not trigger hotkeys.
+    ///
+    /// If several transformations capture an event, the most recently created one is used.
+    /// The transformation is removed when the returned [`Transform`] is dropped.
+    ///
+    /// ```no_run
+    /// use hookmap::prelude::*;
+    ///
+    /// // Types `A` twice each time it is pressed.
+    /// let _transform = Interceptor::transform(Filter::new().target(Button::A), |e| {
+    ///     match e.action {
+    ///         ButtonAction::Press => {
+    ///             let release = ButtonEvent { action: ButtonAction::Release, ..e };
+    ///             vec![e, release, e]
+    ///         }
+    ///         ButtonAction::Release => vec![e],
+    ///     }
+    /// });
+    /// ```
+    ///
+    pub fn transform<T>(filter: F, transform: T) -> Transform<F>
+    where
+        T: Fn(F::Event) -> Vec<F::Event> + Send + Sync + 'static,
+    {
+        let id = broker::<F>().subscribe_transform(Arc::new(filter), Arc::new(transform));
+        Transform {
+            id,
+            _filter: PhantomData,
         }
     }
 
@@ -64,13 +175,59 @@ impl Interceptor {
     /// println!("This event is blocked: {:?}", event);
     /// ```
     ///
-    pub fn dispatch(filter: Filter) -> Self {
+    pub fn dispatch(filter: F) -> Self {
         Self {
             filter: Arc::new(filter),
             native_event_operation: NativeEventOperation::Dispatch,
+            pending: Mutex::default(),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            overflow_policy: OverflowPolicy::default(),
         }
     }
 
+    /// Sets the capacity of the queue that holds events captured by [`Iter`]
+    /// until they are consumed. The default is [`DEFAULT_QUEUE_CAPACITY`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// ```no_run
+    /// use hookmap::prelude::*;
+    ///
+    /// let interceptor = Interceptor::blocking(Filter::new()).queue_capacity(16);
+    /// ```
+    ///
+    pub fn queue_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "The capacity of the queue must not be zero.");
+        self.queue_capacity = capacity;
+        self
+    }
+
+    /// Sets the behavior when an event is captured while the queue of [`Iter`] is full.
+    /// The default is [`OverflowPolicy::Dispatch`].
+    ///
+    /// ```no_run
+    /// use hookmap::prelude::*;
+    /// use hookmap::interceptor::OverflowPolicy;
+    ///
+    /// let interceptor =
+    ///     Interceptor::dispatch(CursorFilter::new()).overflow_policy(OverflowPolicy::DropOldest);
+    /// ```
+    ///
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    fn subscribe(&self) -> Subscription<F> {
+        Subscription::new(Arc::clone(&self.filter), self.native_event_operation)
+    }
+
+    fn pending(&self) -> MutexGuard<'_, Option<Subscription<F>>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
     /// Captures a single event.
     ///
     /// ```no_run
@@ -80,17 +237,76 @@ impl Interceptor {
     /// let event = Interceptor::dispatch(filter).get();
     /// println!("{:?}", event);
     /// ```
-    pub fn get(&self) -> ButtonEvent {
-        let rx = BROKER
-            .lock()
-            .unwrap()
-            .subscribe_once(Arc::clone(&self.filter), self.native_event_operation);
+    pub fn get(&self) -> F::Event {
+        let subscription = self.pending().take().unwrap_or_else(|| self.subscribe());
+        subscription.rx.recv().unwrap()
+    }
 
-        rx.recv().unwrap()
+    /// Captures a single event, waiting at most `timeout`.
+    /// Returns `None` if no event is captured within `timeout`.
+    ///
+    /// When this times out, the interceptor stops capturing events,
+    /// so the subsequent events are not blocked.
+    ///
+    /// ```no_run
+    /// use hookmap::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let filter = Filter::new().action(ButtonAction::Press);
+    /// match Interceptor::blocking(filter).get_timeout(Duration::from_secs(5)) {
+    ///     Some(event) => println!("{:?} will be bound.", event.target),
+    ///     None => println!("Canceled."),
+    /// }
+    /// ```
+    ///
+    pub fn get_timeout(&self, timeout: Duration) -> Option<F::Event> {
+        let subscription = self.pending().take().unwrap_or_else(|| self.subscribe());
+        match subscription.rx.recv_timeout(timeout) {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout) => subscription.cancel(),
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+
+    /// Returns an event if one has been captured, without blocking.
+    ///
+    /// The interceptor starts capturing events at the first call,
+    /// and keeps capturing until an event is returned or the interceptor is dropped.
+    ///
+    /// ```no_run
+    /// use hookmap::prelude::*;
+    /// use std::{thread, time::Duration};
+    ///
+    /// let interceptor = Interceptor::dispatch(Filter::new());
+    /// loop {
+    ///     if let Some(event) = interceptor.try_get() {
+    ///         println!("{:?}", event);
+    ///         break;
+    ///     }
+    ///     thread::sleep(Duration::from_millis(100));
+    /// }
+    /// ```
+    ///
+    pub fn try_get(&self) -> Option<F::Event> {
+        let mut pending = self.pending();
+        let event = pending
+            .get_or_insert_with(|| self.subscribe())
+            .rx
+            .try_recv()
+            .ok();
+        if event.is_some() {
+            *pending = None;
+        }
+        event
     }
 
     /// Captures events with an iterator.
     ///
+    /// The iterator starts capturing events when it is created, and keeps capturing
+    /// until it is dropped. Events captured before [`Iterator::next`] is called are held
+    /// in a queue, whose capacity and overflow behavior are set by
+    /// [`Interceptor::queue_capacity`] and [`Interceptor::overflow_policy`].
+    ///
     /// ```no_run
     /// use hookmap::prelude::*;
     ///
@@ -103,28 +319,177 @@ impl Interceptor {
     ///     .collect();
     /// ```
     ///
-    pub fn iter(&self) -> Iter {
-        Iter {
-            filter: Arc::clone(&self.filter),
-            native_event_operation: self.native_event_operation,
+    pub fn iter(&self) -> Iter<F> {
+        self.subscribe_with_queue(self.queue_capacity, self.overflow_policy)
+    }
+
+    fn subscribe_with_queue(&self, capacity: usize, policy: OverflowPolicy) -> Iter<F> {
+        let queue = Arc::new(Queue::new(capacity, policy));
+        let id = broker::<F>().subscribe(
+            Arc::clone(&self.filter),
+            self.native_event_operation,
+            Arc::clone(&queue),
+        );
+        Iter { id, queue }
+    }
+
+    /// Captures a single event asynchronously.
+    ///
+    /// The interceptor starts capturing when this is called,
+    /// and stops when the returned future completes or is dropped.
+    ///
+    /// ```no_run
+    /// use hookmap::prelude::*;
+    ///
+    /// async fn bind_key() -> Button {
+    ///     let filter = Filter::new().action(ButtonAction::Press);
+    ///     Interceptor::blocking(filter).get_async().await.target
+    /// }
+    /// ```
+    ///
+    #[cfg(feature = "async")]
+    pub fn get_async(&self) -> Get<F> {
+        Get {
+            iter: Some(self.subscribe_with_queue(1, OverflowPolicy::Dispatch)),
         }
     }
+
+    /// Captures events with a [`Stream`](futures_core::Stream).
+    ///
+    /// This captures events in the same way as [`Interceptor::iter`].
+    ///
+    /// ```no_run
+    /// use hookmap::prelude::*;
+    /// use hookmap::interceptor::EventStream;
+    ///
+    /// fn key_presses() -> EventStream {
+    ///     Interceptor::dispatch(Filter::new().action(ButtonAction::Press)).stream()
+    /// }
+    /// ```
+    ///
+    #[cfg(feature = "async")]
+    pub fn stream(&self) -> EventStream<F> {
+        EventStream(self.iter())
+    }
 }
 
-pub struct Iter {
-    filter: Arc<Filter>,
-    native_event_operation: NativeEventOperation,
+/// A transformation created by [`Interceptor::transform`].
+///
+/// The transformation is removed when this is dropped.
+#[derive(Debug)]
+pub struct Transform<F: EventFilter = Filter> {
+    id: SubscriptionId,
+    _filter: PhantomData<fn() -> F>,
+}
+
+impl<F: EventFilter> Drop for Transform<F> {
+    fn drop(&mut self) {
+        broker::<F>().unsubscribe(self.id);
+    }
+}
+
+/// An iterator over captured events.
+///
+/// The subscription is removed when this iterator is dropped,
+/// so an abandoned iterator does not capture events.
+/// Events that are captured but not consumed yet are discarded.
+#[derive(Debug)]
+pub struct Iter<F: EventFilter = Filter> {
+    id: SubscriptionId,
+    queue: Arc<Queue<F::Event>>,
 }
 
-impl Iterator for Iter {
-    type Item = ButtonEvent;
+impl<F: EventFilter> Iter<F> {
+    /// Returns the next event, waiting at most `timeout`.
+    ///
+    /// ```no_run
+    /// use hookmap::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let interceptor = Interceptor::dispatch(Filter::new());
+    /// let mut iter = interceptor.iter();
+    /// while let Some(event) = iter.next_timeout(Duration::from_secs(1)) {
+    ///     println!("{:?}", event);
+    /// }
+    /// ```
+    ///
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<F::Event> {
+        self.queue.pop_timeout(timeout)
+    }
+
+    /// Returns the next event and the time when it was captured, waiting at most `timeout`.
+    pub(crate) fn next_timeout_with_time(
+        &mut self,
+        timeout: Duration,
+    ) -> Option<(Instant, F::Event)> {
+        self.queue.pop_timeout_with_time(timeout)
+    }
+
+    /// Returns the next event if it has been captured, without blocking.
+    pub fn try_next(&mut self) -> Option<F::Event> {
+        self.queue.try_pop()
+    }
+}
+
+impl<F: EventFilter> Iterator for Iter<F> {
+    type Item = F::Event;
+
+    fn next(&mut self) -> Option<F::Event> {
+        Some(self.queue.pop())
+    }
+}
+
+impl<F: EventFilter> Drop for Iter<F> {
+    fn drop(&mut self) {
+        broker::<F>().unsubscribe(self.id);
+        self.queue.close();
+    }
+}
+
+/// A future returned by [`Interceptor::get_async`].
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct Get<F: EventFilter = Filter> {
+    iter: Option<Iter<F>>,
+}
+
+#[cfg(feature = "async")]
+impl<F: EventFilter> Future for Get<F> {
+    type Output = F::Event;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Event> {
+        let iter = self.iter.as_ref().expect("`Get` polled after completion");
+        let event = iter.queue.poll_pop(cx);
+        if event.is_ready() {
+            self.iter = None;
+        }
+        event
+    }
+}
+
+/// A stream of captured events returned by [`Interceptor::stream`].
+///
+/// Like [`Iter`], the subscription is removed when this stream is dropped.
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct EventStream<F: EventFilter = Filter>(Iter<F>);
+
+#[cfg(feature = "async")]
+impl<F: EventFilter> futures_core::Stream for EventStream<F> {
+    type Item = F::Event;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<F::Event>> {
+        self.0.queue.poll_pop(cx).map(Some)
+    }
+}
 
-    fn next(&mut self) -> Option<ButtonEvent> {
-        let rx = BROKER
-            .lock()
-            .unwrap()
-            .subscribe_once(Arc::clone(&self.filter), self.native_event_operation);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        rx.recv().ok()
+    #[test]
+    #[should_panic]
+    fn zero_queue_capacity_is_rejected() {
+        let _ = Interceptor::dispatch(Filter::new()).queue_capacity(0);
     }
 }
@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::task::Waker;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+
+/// Behavior when an event is captured while the queue of an [`Iter`] is full.
+///
+/// [`Iter`]: super::interceptor::Iter
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Discards the oldest event in the queue and queues the new one.
+    DropOldest,
+
+    /// Discards the new event.
+    /// In blocking mode, the discarded event is still blocked.
+    DropNewest,
+
+    /// Does not capture the new event.
+    /// In blocking mode, the event is passed to other interceptors, hotkeys and applications.
+    #[default]
+    Dispatch,
+}
+
+#[derive(Debug)]
+struct State<E> {
+    // Events with the time when they were pushed.
+    events: VecDeque<(Instant, E)>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+/// A bounded queue shared by the broker and a persistent subscriber.
+#[derive(Debug)]
+pub(super) struct Queue<E> {
+    state: Mutex<State<E>>,
+    condvar: Condvar,
+    capacity: usize,
+    policy: OverflowPolicy,
+}
+
+impl<E> Queue<E> {
+    pub(super) fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            state: Mutex::new(State {
+                events: VecDeque::with_capacity(capacity),
+                closed: false,
+                waker: None,
+            }),
+            condvar: Condvar::new(),
+            capacity,
+            policy,
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State<E>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Pushes the event according to the overflow policy.
+    /// Returns `true` if the event is taken by this queue, even if it is discarded.
+    pub(super) fn push(&self, event: E) -> bool {
+        let mut state = self.state();
+        if state.closed {
+            return false;
+        }
+        if state.events.len() >= self.capacity {
+            match self.policy {
+                OverflowPolicy::DropOldest => {
+                    state.events.pop_front();
+                }
+                OverflowPolicy::DropNewest => return true,
+                OverflowPolicy::Dispatch => return false,
+            }
+        }
+        if state.events.len() < self.capacity {
+            state.events.push_back((Instant::now(), event));
+        }
+        self.condvar.notify_one();
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        true
+    }
+
+    /// Waits for an event.
+    pub(super) fn pop(&self) -> E {
+        let mut state = self.state();
+        loop {
+            if let Some((_, event)) = state.events.pop_front() {
+                return event;
+            }
+            state = self
+                .condvar
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Waits for an event at most `timeout`.
+    pub(super) fn pop_timeout(&self, timeout: Duration) -> Option<E> {
+        self.pop_timeout_with_time(timeout).map(|(_, event)| event)
+    }
+
+    /// Waits for an event at most `timeout`, and returns it with the time when it was pushed.
+    pub(super) fn pop_timeout_with_time(&self, timeout: Duration) -> Option<(Instant, E)> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state();
+        loop {
+            if let Some(entry) = state.events.pop_front() {
+                return Some(entry);
+            }
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+            state = self
+                .condvar
+                .wait_timeout(state, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Pops an event, or registers the waker to be woken when an event is pushed.
+    #[cfg(feature = "async")]
+    pub(super) fn poll_pop(&self, cx: &mut Context<'_>) -> Poll<E> {
+        let mut state = self.state();
+        match state.events.pop_front() {
+            Some((_, event)) => Poll::Ready(event),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    pub(super) fn try_pop(&self) -> Option<E> {
+        self.state().events.pop_front().map(|(_, event)| event)
+    }
+
+    /// Stops accepting events. This is called when the subscriber is dropped.
+    pub(super) fn close(&self) {
+        let mut state = self.state();
+        state.closed = true;
+        state.events.clear();
+    }
+
+    pub(super) fn is_closed(&self) -> bool {
+        self.state().closed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue_with(policy: OverflowPolicy) -> Queue<i32> {
+        let queue = Queue::new(2, policy);
+        assert!(queue.push(1));
+        assert!(queue.push(2));
+        queue
+    }
+
+    #[test]
+    fn drop_oldest_discards_the_front_event() {
+        let queue = queue_with(OverflowPolicy::DropOldest);
+        assert!(queue.push(3));
+        assert_eq!(queue.try_pop(), Some(2));
+        assert_eq!(queue.try_pop(), Some(3));
+        assert_eq!(queue.try_pop(), None);
+    }
+
+    #[test]
+    fn drop_newest_takes_and_discards_the_new_event() {
+        let queue = queue_with(OverflowPolicy::DropNewest);
+        assert!(queue.push(3));
+        assert_eq!(queue.try_pop(), Some(1));
+        assert_eq!(queue.try_pop(), Some(2));
+        assert_eq!(queue.try_pop(), None);
+    }
+
+    #[test]
+    fn dispatch_rejects_the_new_event() {
+        let queue = queue_with(OverflowPolicy::Dispatch);
+        assert!(!queue.push(3));
+        assert_eq!(queue.try_pop(), Some(1));
+    }
+
+    #[test]
+    fn closed_queue_rejects_events() {
+        let queue = Queue::new(1, OverflowPolicy::DropOldest);
+        queue.close();
+        assert!(!queue.push(1));
+        assert_eq!(queue.pop_timeout(Duration::ZERO), None);
+    }
+}
@@ -0,0 +1,75 @@
+use hookmap_core::kill_switch;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static IS_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+/// Suspends all hotkeys and interceptors.
+/// While suspended, all events are passed to other programs untouched,
+/// except for the hotkeys registered by [`Registrar::toggle_suspension`].
+///
+/// Unlike uninstalling hotkeys, the hook to the OS remains installed.
+///
+/// [`Registrar::toggle_suspension`]: crate::hotkey::Registrar::toggle_suspension
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::hotkey;
+///
+/// hotkey::suspend();
+/// assert!(hotkey::is_suspended());
+/// ```
+///
+pub fn suspend() {
+    IS_SUSPENDED.store(true, Ordering::SeqCst);
+}
+
+/// Resumes hotkeys and interceptors suspended by [`suspend`].
+///
+/// This also resets the kill switch of `hookmap_core`.
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::hotkey;
+///
+/// hotkey::suspend();
+/// hotkey::resume();
+/// assert!(!hotkey::is_suspended());
+/// ```
+///
+pub fn resume() {
+    IS_SUSPENDED.store(false, Ordering::SeqCst);
+    kill_switch::reset();
+}
+
+/// Suspends hotkeys if they are running, otherwise resumes them.
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::hotkey;
+///
+/// hotkey::toggle_suspension();
+/// ```
+///
+pub fn toggle_suspension() {
+    if is_suspended() {
+        resume();
+    } else {
+        suspend();
+    }
+}
+
+/// Returns `true` if hotkeys are suspended.
+///
+/// This is also `true` after the kill switch of `hookmap_core` is triggered.
+/// In this case, no events are passed to hookmap until [`resume`] is called,
+/// so hotkeys registered by [`Registrar::toggle_suspension`] do not work either.
+///
+/// [`Registrar::toggle_suspension`]: crate::hotkey::Registrar::toggle_suspension
+///
+pub fn is_suspended() -> bool {
+    IS_SUSPENDED.load(Ordering::SeqCst) || kill_switch::is_triggered()
+}
@@ -1,5 +1,9 @@
 //! Definition of utility hotkeys.
 
+mod mouse_keys;
+
+pub use self::mouse_keys::{Acceleration, MouseKeys};
+
 use crate::macros::button_arg::ButtonArg;
 use crate::macros::sequence::Sequence;
 use crate::prelude::*;
@@ -75,3 +79,30 @@ pub fn shift_alt_tab(
 ) {
     bind_alt_tab_inner(hotkey, context, alt, tab, seq!(with(LShift), Tab));
 }
+
+/// Mouse keys hotkey that moves and clicks the mouse cursor from the keyboard.
+///
+/// While the movement or scroll buttons of `mouse_keys` are held, the cursor moves or the wheel
+/// scrolls smoothly, and it stops when they are released or `context` is no longer satisfied.
+/// These buttons are disabled while `context` is satisfied.
+///
+/// # Example
+///
+/// ```
+/// use hookmap::prelude::*;
+/// use hookmap::utils::MouseKeys;
+///
+/// let mut hotkey = Hotkey::new();
+/// let mouse_keys = MouseKeys::new()
+///     .movement(Button::I, Button::K, Button::J, Button::L)
+///     .click(Button::Space, Button::LeftButton);
+/// utils::mouse_keys(
+///     &mut hotkey,
+///     &Context::new().modifiers(buttons!(LAlt)),
+///     mouse_keys,
+/// );
+/// ```
+///
+pub fn mouse_keys(hotkey: &mut Hotkey, context: &Context, mouse_keys: MouseKeys) {
+    self::mouse_keys::register(hotkey, context, mouse_keys);
+}
@@ -0,0 +1,347 @@
+use crate::hook::ButtonState;
+use crate::prelude::*;
+use crate::runtime::RealButtonState;
+
+use hookmap_core::event::{WheelAxis, WHEEL_DELTA};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How the speed of the mouse cursor changes while the movement keys are held.
+///
+/// The speed grows from `initial_speed` to `max_speed` over `time_to_max`,
+/// following `t^exponent` where `t` is the elapsed fraction of `time_to_max`.
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::utils::Acceleration;
+/// use std::time::Duration;
+///
+/// // Starts at 100 px/s and reaches 2000 px/s after 1.5 seconds, slowly at first.
+/// let acceleration = Acceleration::new(100.0, 2000.0, Duration::from_millis(1500)).exponent(3.0);
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Acceleration {
+    initial_speed: f64,
+    max_speed: f64,
+    time_to_max: Duration,
+    exponent: f64,
+}
+
+impl Default for Acceleration {
+    fn default() -> Self {
+        Self::new(200.0, 1500.0, Duration::from_secs(1)).exponent(2.0)
+    }
+}
+
+impl Acceleration {
+    /// Creates an acceleration that grows linearly from `initial_speed` to `max_speed`.
+    /// The speeds are in pixels per second.
+    pub fn new(initial_speed: f64, max_speed: f64, time_to_max: Duration) -> Self {
+        Self {
+            initial_speed,
+            max_speed,
+            time_to_max,
+            exponent: 1.0,
+        }
+    }
+
+    /// Creates an acceleration that keeps `speed` pixels per second.
+    pub fn constant(speed: f64) -> Self {
+        Self::new(speed, speed, Duration::ZERO)
+    }
+
+    /// Sets the shape of the curve. Values greater than `1.0` accelerate slowly at first.
+    pub fn exponent(mut self, exponent: f64) -> Self {
+        self.exponent = exponent;
+        self
+    }
+
+    fn speed(&self, elapsed: Duration) -> f64 {
+        let progress = if self.time_to_max.is_zero() {
+            1.0
+        } else {
+            (elapsed.as_secs_f64() / self.time_to_max.as_secs_f64()).min(1.0)
+        };
+        self.initial_speed + (self.max_speed - self.initial_speed) * progress.powf(self.exponent)
+    }
+}
+
+/// Buttons for each direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Directions {
+    up: Option<Button>,
+    down: Option<Button>,
+    left: Option<Button>,
+    right: Option<Button>,
+}
+
+impl Directions {
+    fn buttons(&self) -> impl Iterator<Item = Button> {
+        [self.up, self.down, self.left, self.right]
+            .into_iter()
+            .flatten()
+    }
+
+    /// Returns the direction `(x, y)` of the pressed buttons, whose length is `1.0` or `0.0`.
+    /// `y` is positive downward.
+    fn vector(&self, is_pressed: impl Fn(Button) -> bool) -> (f64, f64) {
+        let value = |button: Option<Button>| match button {
+            Some(button) if is_pressed(button) => 1.0,
+            _ => 0.0,
+        };
+        let x: f64 = value(self.right) - value(self.left);
+        let y: f64 = value(self.down) - value(self.up);
+        let length = x.hypot(y);
+        if length == 0.0 {
+            (0.0, 0.0)
+        } else {
+            (x / length, y / length)
+        }
+    }
+}
+
+/// Configuration of [`mouse_keys`](super::mouse_keys).
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::prelude::*;
+/// use hookmap::utils::{Acceleration, MouseKeys};
+///
+/// let mouse_keys = MouseKeys::new()
+///     .movement(Button::I, Button::K, Button::J, Button::L)
+///     .scroll(Button::U, Button::O, Button::Y, Button::P)
+///     .click(Button::Space, Button::LeftButton)
+///     .click(Button::N, Button::RightButton)
+///     .acceleration(Acceleration::constant(800.0));
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct MouseKeys {
+    movement: Directions,
+    scroll: Directions,
+    clicks: Vec<(Button, Button)>,
+    acceleration: Acceleration,
+    scroll_speed: f64,
+}
+
+impl Default for MouseKeys {
+    fn default() -> Self {
+        Self {
+            movement: Directions::default(),
+            scroll: Directions::default(),
+            clicks: Vec::new(),
+            acceleration: Acceleration::default(),
+            scroll_speed: 10.0,
+        }
+    }
+}
+
+impl MouseKeys {
+    const TICK: Duration = Duration::from_millis(10);
+
+    /// Creates a new instance of [`MouseKeys`] without any buttons.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the buttons that move the mouse cursor.
+    pub fn movement(mut self, up: Button, down: Button, left: Button, right: Button) -> Self {
+        self.movement = Directions {
+            up: Some(up),
+            down: Some(down),
+            left: Some(left),
+            right: Some(right),
+        };
+        self
+    }
+
+    /// Sets the buttons that scroll with the mouse wheel.
+    pub fn scroll(mut self, up: Button, down: Button, left: Button, right: Button) -> Self {
+        self.scroll = Directions {
+            up: Some(up),
+            down: Some(down),
+            left: Some(left),
+            right: Some(right),
+        };
+        self
+    }
+
+    /// Makes `key` act as `mouse_button`. Holding `key` holds `mouse_button`, so it can drag.
+    pub fn click(mut self, key: Button, mouse_button: Button) -> Self {
+        self.clicks.push((key, mouse_button));
+        self
+    }
+
+    /// Sets the acceleration of the mouse cursor.
+    pub fn acceleration(mut self, acceleration: Acceleration) -> Self {
+        self.acceleration = acceleration;
+        self
+    }
+
+    /// Sets the scrolling speed in notches per second.
+    pub fn scroll_speed(mut self, notches_per_second: f64) -> Self {
+        self.scroll_speed = notches_per_second;
+        self
+    }
+
+    /// Returns `true` if the conditions of `context` are satisfied and any of the movement or
+    /// scroll buttons is pressed.
+    fn is_active(&self, context: &Context, state: &impl ButtonState) -> bool {
+        context.is_satisfied(state)
+            && self
+                .movement
+                .buttons()
+                .chain(self.scroll.buttons())
+                .any(|button| state.is_pressed(button))
+    }
+
+    /// Moves the cursor and scrolls while the buttons are held and `context` is satisfied.
+    fn run(&self, context: &Context, running: &AtomicBool) {
+        let state = RealButtonState;
+        let mut last = Instant::now();
+        let mut movement_start = None;
+        let mut movement_remainder = (0.0, 0.0);
+        let mut scroll_remainder = (0.0, 0.0);
+
+        loop {
+            thread::sleep(Self::TICK);
+            if !self.is_active(context, &state) {
+                running.store(false, Ordering::SeqCst);
+                // A button may have been pressed after the check above while `running` was set.
+                if !self.is_active(context, &state) || running.swap(true, Ordering::SeqCst) {
+                    return;
+                }
+            }
+
+            let now = Instant::now();
+            let elapsed = (now - last).as_secs_f64();
+            last = now;
+
+            let (x, y) = self.movement.vector(Button::is_pressed);
+            if (x, y) == (0.0, 0.0) {
+                movement_start = None;
+            } else {
+                let start = *movement_start.get_or_insert(now);
+                let distance = self.acceleration.speed(now - start) * elapsed;
+                let (dx, dy) = take_whole(&mut movement_remainder, (x * distance, y * distance));
+                if (dx, dy) != (0, 0) {
+                    mouse::move_relative(dx, dy);
+                }
+            }
+
+            let (x, y) = self.scroll.vector(Button::is_pressed);
+            let distance = self.scroll_speed * f64::from(WHEEL_DELTA) * elapsed;
+            // The vertical wheel scrolls upward with positive values.
+            let (horizontal, vertical) =
+                take_whole(&mut scroll_remainder, (x * distance, -y * distance));
+            if horizontal != 0 {
+                mouse::rotate_hi_res(WheelAxis::Horizontal, horizontal);
+            }
+            if vertical != 0 {
+                mouse::rotate_hi_res(WheelAxis::Vertical, vertical);
+            }
+        }
+    }
+}
+
+/// Adds `delta` to `remainder` and takes the whole part out of it.
+fn take_whole(remainder: &mut (f64, f64), delta: (f64, f64)) -> (i32, i32) {
+    let (x, y) = (remainder.0 + delta.0, remainder.1 + delta.1);
+    let (whole_x, whole_y) = (x.trunc(), y.trunc());
+    *remainder = (x - whole_x, y - whole_y);
+    (whole_x as i32, whole_y as i32)
+}
+
+pub(super) fn register(hotkey: &mut Hotkey, context: &Context, mouse_keys: MouseKeys) {
+    let mouse_keys = Arc::new(mouse_keys);
+    let context = Arc::new(context.clone());
+    let running = Arc::new(AtomicBool::new(false));
+
+    let mut registrar = hotkey.register(Context::clone(&context));
+    for key in mouse_keys
+        .movement
+        .buttons()
+        .chain(mouse_keys.scroll.buttons())
+    {
+        let mouse_keys = Arc::clone(&mouse_keys);
+        let context = Arc::clone(&context);
+        let running = Arc::clone(&running);
+        registrar.disable(key).on_press(key, move |_| {
+            if !running.swap(true, Ordering::SeqCst) {
+                let mouse_keys = Arc::clone(&mouse_keys);
+                let context = Arc::clone(&context);
+                let running = Arc::clone(&running);
+                thread::spawn(move || mouse_keys.run(&context, &running));
+            }
+        });
+    }
+    for &(key, mouse_button) in &mouse_keys.clicks {
+        registrar.remap(key, mouse_button);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acceleration_follows_curve() {
+        let acceleration = Acceleration::new(100.0, 500.0, Duration::from_secs(2)).exponent(2.0);
+        assert_eq!(acceleration.speed(Duration::ZERO), 100.0);
+        assert_eq!(acceleration.speed(Duration::from_secs(1)), 200.0);
+        assert_eq!(acceleration.speed(Duration::from_secs(2)), 500.0);
+        assert_eq!(acceleration.speed(Duration::from_secs(5)), 500.0);
+        assert_eq!(Acceleration::constant(300.0).speed(Duration::ZERO), 300.0);
+    }
+
+    #[test]
+    fn diagonal_movement_is_normalized() {
+        let directions = Directions {
+            up: Some(Button::I),
+            down: Some(Button::K),
+            left: Some(Button::J),
+            right: Some(Button::L),
+        };
+        let vector = |pressed: &[Button]| directions.vector(|button| pressed.contains(&button));
+        assert_eq!(vector(&[]), (0.0, 0.0));
+        assert_eq!(vector(&[Button::I, Button::K]), (0.0, 0.0));
+        assert_eq!(vector(&[Button::J]), (-1.0, 0.0));
+        let (x, y) = vector(&[Button::K, Button::L]);
+        assert!((x - y).abs() < f64::EPSILON && (x.hypot(y) - 1.0).abs() < 1e-9);
+    }
+
+    struct Pressed(Vec<Button>);
+
+    impl ButtonState for Pressed {
+        fn is_pressed(&self, button: Button) -> bool {
+            self.0.contains(&button)
+        }
+
+        fn is_released(&self, button: Button) -> bool {
+            !self.is_pressed(button)
+        }
+    }
+
+    #[test]
+    fn movement_stops_when_context_is_not_satisfied() {
+        let mouse_keys = MouseKeys::new().movement(Button::I, Button::K, Button::J, Button::L);
+        let context = Context::new().modifiers(buttons!(LShift));
+        assert!(mouse_keys.is_active(&context, &Pressed(vec![Button::LShift, Button::J])));
+        assert!(!mouse_keys.is_active(&context, &Pressed(vec![Button::J])));
+        assert!(!mouse_keys.is_active(&context, &Pressed(vec![Button::LShift])));
+    }
+
+    #[test]
+    fn fractions_are_carried_over() {
+        let mut remainder = (0.0, 0.0);
+        assert_eq!(take_whole(&mut remainder, (0.6, -0.6)), (0, 0));
+        assert_eq!(take_whole(&mut remainder, (0.6, -0.6)), (1, -1));
+        assert_eq!(take_whole(&mut remainder, (0.8, -0.8)), (1, -1));
+    }
+}
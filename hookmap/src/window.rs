@@ -0,0 +1,304 @@
+//! Conditions on the foreground window.
+//!
+//! Hotkeys can be limited to the applications with [`Context::when_window`].
+//! The foreground window is queried through a [`WindowInfoProvider`], which can be replaced
+//! by [`FakeWindowInfo`] with [`set_provider`] to test hotkeys without real windows.
+//!
+//! [`SystemWindowInfo`] supports Windows and X11. Wayland is not supported.
+//!
+//! [`Context::when_window`]: crate::hotkey::Context::when_window
+//!
+//! # Examples
+//!
+//! ```
+//! use hookmap::prelude::*;
+//! use hookmap::window::WindowMatcher;
+//!
+//! let mut hotkey = Hotkey::new();
+//! hotkey
+//!     .register(Context::new().when_window(WindowMatcher::new().process_name("notepad.exe")))
+//!     .remap(Button::F1, seq!(with(LCtrl), S));
+//! ```
+//!
+
+pub use hookmap_core::window::WindowInfo;
+
+use std::fmt::{self, Debug};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+/// Provides the information about the foreground window.
+pub trait WindowInfoProvider: Send + Sync {
+    /// Returns the foreground window, or `None` if no window is in the foreground.
+    fn foreground_window(&self) -> Option<WindowInfo>;
+}
+
+/// Queries the OS for the foreground window. This is the default provider.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemWindowInfo;
+
+impl WindowInfoProvider for SystemWindowInfo {
+    fn foreground_window(&self) -> Option<WindowInfo> {
+        hookmap_core::window::foreground_window()
+    }
+}
+
+/// A provider that returns the window set by [`FakeWindowInfo::set`].
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::window::{self, FakeWindowInfo, WindowInfo};
+///
+/// let fake = FakeWindowInfo::new();
+/// window::set_provider(fake.clone());
+/// fake.set(Some(WindowInfo {
+///     process_name: "notepad.exe".to_owned(),
+///     ..Default::default()
+/// }));
+/// assert_eq!(window::foreground_window().unwrap().process_name, "notepad.exe");
+/// ```
+///
+#[derive(Debug, Default, Clone)]
+pub struct FakeWindowInfo(Arc<Mutex<Option<WindowInfo>>>);
+
+impl FakeWindowInfo {
+    /// Creates a new instance of [`FakeWindowInfo`] without a foreground window.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the foreground window.
+    pub fn set(&self, window: Option<WindowInfo>) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = window;
+        CACHE.lock().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+}
+
+impl WindowInfoProvider for FakeWindowInfo {
+    fn foreground_window(&self) -> Option<WindowInfo> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+#[derive(Default)]
+struct Cache {
+    window: Option<Arc<WindowInfo>>,
+    updated_at: Option<Instant>,
+}
+
+impl Cache {
+    // The foreground window rarely changes between consecutive events,
+    // so it is not queried on every mouse movement.
+    const DURATION: Duration = Duration::from_millis(100);
+
+    fn clear(&mut self) {
+        self.updated_at = None;
+    }
+
+    fn get(&self, now: Instant) -> Option<Option<Arc<WindowInfo>>> {
+        match self.updated_at {
+            Some(updated_at) if now - updated_at < Self::DURATION => Some(self.window.clone()),
+            _ => None,
+        }
+    }
+}
+
+static PROVIDER: Lazy<RwLock<Arc<dyn WindowInfoProvider>>> =
+    Lazy::new(|| RwLock::new(Arc::new(SystemWindowInfo)));
+
+static CACHE: Lazy<Mutex<Cache>> = Lazy::new(Mutex::default);
+
+/// Replaces the provider of the foreground window.
+pub fn set_provider(provider: impl WindowInfoProvider + 'static) {
+    *PROVIDER.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(provider);
+    CACHE.lock().unwrap_or_else(PoisonError::into_inner).clear();
+}
+
+/// Returns the foreground window.
+/// The result is cached for a short time, so it may not reflect a change made just before.
+pub fn foreground_window() -> Option<Arc<WindowInfo>> {
+    let now = Instant::now();
+    if let Some(window) = CACHE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(now)
+    {
+        return window;
+    }
+
+    // The provider is queried without the lock, since querying the OS may take a while.
+    let provider = Arc::clone(&PROVIDER.read().unwrap_or_else(PoisonError::into_inner));
+    let window = provider.foreground_window().map(Arc::new);
+    let mut cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    cache.window = window.clone();
+    cache.updated_at = Some(now);
+    window
+}
+
+type Predicate = Arc<dyn Fn(&WindowInfo) -> bool + Send + Sync>;
+
+/// Matches the foreground window. This is passed to [`Context::when_window`].
+///
+/// A window matches if it satisfies all the conditions.
+/// Process names and class names are compared case-insensitively.
+///
+/// [`Context::when_window`]: crate::hotkey::Context::when_window
+///
+/// # Examples
+///
+/// ```
+/// use hookmap::window::{WindowInfo, WindowMatcher};
+///
+/// let matcher = WindowMatcher::new()
+///     .process_name("firefox.exe")
+///     .title_contains("YouTube");
+/// let window = WindowInfo {
+///     process_name: "Firefox.exe".to_owned(),
+///     title: "YouTube - Mozilla Firefox".to_owned(),
+///     ..Default::default()
+/// };
+/// assert!(matcher.is_match(&window));
+/// ```
+///
+#[derive(Clone, Default)]
+pub struct WindowMatcher {
+    process_name: Option<String>,
+    executable_path: Option<PathBuf>,
+    class: Option<String>,
+    title: Option<String>,
+    predicates: Vec<Predicate>,
+}
+
+impl Debug for WindowMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowMatcher")
+            .field("process_name", &self.process_name)
+            .field("executable_path", &self.executable_path)
+            .field("class", &self.class)
+            .field("title", &self.title)
+            .field("predicates", &self.predicates.len())
+            .finish()
+    }
+}
+
+impl WindowMatcher {
+    /// Creates a new instance of [`WindowMatcher`] that matches any window.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches the windows of the processes with the executable file name `name`.
+    pub fn process_name(mut self, name: impl Into<String>) -> Self {
+        self.process_name = Some(name.into());
+        self
+    }
+
+    /// Matches the windows of the processes with the executable at `path`.
+    pub fn executable_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.executable_path = Some(path.into());
+        self
+    }
+
+    /// Matches the windows with the class name `class`.
+    pub fn class(mut self, class: impl Into<String>) -> Self {
+        self.class = Some(class.into());
+        self
+    }
+
+    /// Matches the windows whose title contains `text`.
+    pub fn title_contains(mut self, text: impl Into<String>) -> Self {
+        self.title = Some(text.into());
+        self
+    }
+
+    /// Matches the windows for which `predicate` returns `true`.
+    pub fn matches<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&WindowInfo) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.push(Arc::new(predicate));
+        self
+    }
+
+    /// Returns `true` if `window` satisfies all the conditions.
+    pub fn is_match(&self, window: &WindowInfo) -> bool {
+        self.process_name
+            .iter()
+            .all(|name| name.eq_ignore_ascii_case(&window.process_name))
+            && self
+                .executable_path
+                .iter()
+                .all(|path| *path == window.executable_path)
+            && self
+                .class
+                .iter()
+                .all(|class| class.eq_ignore_ascii_case(&window.class))
+            && self
+                .title
+                .iter()
+                .all(|title| window.title.contains(title.as_str()))
+            && self.predicates.iter().all(|predicate| predicate(window))
+    }
+
+    /// Returns `true` if the foreground window matches.
+    pub(crate) fn is_foreground(&self) -> bool {
+        match foreground_window() {
+            Some(window) => self.is_match(&window),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notepad() -> WindowInfo {
+        WindowInfo {
+            process_name: "notepad.exe".to_owned(),
+            executable_path: PathBuf::from(r"C:\Windows\notepad.exe"),
+            class: "Notepad".to_owned(),
+            title: "memo.txt - Notepad".to_owned(),
+        }
+    }
+
+    #[test]
+    fn matching_windows() {
+        assert!(WindowMatcher::new().is_match(&notepad()));
+        assert!(WindowMatcher::new()
+            .process_name("NOTEPAD.EXE")
+            .class("notepad")
+            .title_contains("memo")
+            .is_match(&notepad()));
+        assert!(WindowMatcher::new()
+            .executable_path(r"C:\Windows\notepad.exe")
+            .is_match(&notepad()));
+        assert!(!WindowMatcher::new()
+            .process_name("notepad.exe")
+            .title_contains("Untitled")
+            .is_match(&notepad()));
+        assert!(!WindowMatcher::new()
+            .matches(|window| window.title.is_empty())
+            .is_match(&notepad()));
+    }
+
+    #[test]
+    fn matching_foreground_window_of_fake_provider() {
+        let fake = FakeWindowInfo::new();
+        set_provider(fake.clone());
+        let matcher = WindowMatcher::new().process_name("notepad.exe");
+        assert!(!matcher.is_foreground());
+
+        fake.set(Some(notepad()));
+        assert!(matcher.is_foreground());
+        fake.set(None);
+        assert!(!matcher.is_foreground());
+    }
+}